@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion, Throughput};
-use rand_core::RngCore;
+use rand_core_06::RngCore;
 
 fn bench_rdrand(c: &mut Criterion) {
     let mut gen = match rdrand::RdRand::new() {
@@ -83,5 +83,31 @@ fn bench_rdseed(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_rdrand, bench_rdseed);
+#[cfg(feature = "chacha")]
+fn bench_chacha(c: &mut Criterion) {
+    let mut gen = match rdrand::ChaChaRdRng::new() {
+        Ok(g) => g,
+        Err(_) => return,
+    };
+    let mut group = c.benchmark_group("chacha");
+
+    group
+        .throughput(Throughput::Bytes(8))
+        .bench_function("next/u64", |b| b.iter(|| gen.next_u64()));
+    let mut buffer = [0; 128];
+    group
+        .throughput(Throughput::Bytes(128))
+        .bench_function("next/fill128", |b| b.iter(|| gen.fill_bytes(&mut buffer)));
+    let mut buffer = vec![0; 1 << 20];
+    group
+        .throughput(Throughput::Bytes(1 << 20))
+        .bench_function("next/fill1M", |b| b.iter(|| gen.fill_bytes(&mut buffer)));
+
+    group.finish();
+}
+
+#[cfg(not(feature = "chacha"))]
+fn bench_chacha(_: &mut Criterion) {}
+
+criterion_group!(benches, bench_rdrand, bench_rdseed, bench_chacha);
 criterion_main!(benches);
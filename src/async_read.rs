@@ -0,0 +1,248 @@
+use crate::fork::ForkGuard;
+use crate::{EntropySource, ErrorCode, RdSeed, MAX_EMPTY_POLLS};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::io;
+use std::vec;
+use std::vec::Vec;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::task::JoinHandle;
+
+/// The default size of the reads performed on the blocking thread pool.
+const DEFAULT_BLOCKING_THRESHOLD: usize = 4096;
+
+/// The source and the data it produced on the blocking thread pool.
+type BlockingRead<S> = JoinHandle<(S, Result<Vec<u8>, ErrorCode>)>;
+
+/// An adapter implementing [`tokio::io::AsyncRead`] on top of [`RdSeed`] (or another
+/// [`EntropySource`]).
+///
+/// Small reads are served directly without blocking the executor: whenever the source runs out
+/// of entropy, the task yields and resumes the next time it is polled. If the source produces no
+/// data for 128 consecutive polls, the read fails. Reads of at least 4096 bytes (see
+/// [`set_blocking_threshold`]) are instead performed on tokio's blocking thread pool in their
+/// entirety, so they must be polled within a tokio runtime.
+///
+/// Errors are reported as described for [`RdReader`](crate::RdReader). The reader never reaches
+/// the end of the stream.
+///
+/// This reader is only available with the `tokio` feature enabled.
+///
+/// [`set_blocking_threshold`]: AsyncRdReader::set_blocking_threshold
+pub struct AsyncRdReader<S = RdSeed> {
+    /// The source is moved to the blocking task while it runs.
+    source: Option<S>,
+    blocking: Option<BlockingRead<S>>,
+    /// The bytes `leftover[pos..]` were produced by the blocking task, but not read yet.
+    leftover: Vec<u8>,
+    pos: usize,
+    blocking_threshold: usize,
+    empty_polls: u32,
+    fork: ForkGuard,
+}
+
+impl AsyncRdReader {
+    /// Create a new reader producing data from [`RdSeed`].
+    ///
+    /// An error is returned if the `rdseed` instruction is not supported.
+    pub fn new() -> Result<Self, ErrorCode> {
+        RdSeed::new().map(Self::with_source)
+    }
+}
+
+impl<S: EntropySource> AsyncRdReader<S> {
+    /// Create a new reader producing data from `source`.
+    pub fn with_source(source: S) -> Self {
+        AsyncRdReader {
+            source: Some(source),
+            blocking: None,
+            leftover: Vec::new(),
+            pos: 0,
+            blocking_threshold: DEFAULT_BLOCKING_THRESHOLD,
+            empty_polls: 0,
+            fork: ForkGuard::new(),
+        }
+    }
+
+    /// Set the size from which reads are performed on the blocking thread pool.
+    ///
+    /// The default is 4096 bytes.
+    pub fn set_blocking_threshold(&mut self, bytes: usize) {
+        self.blocking_threshold = bytes;
+    }
+
+    fn read_leftover(&mut self, buf: &mut ReadBuf<'_>) {
+        let available = &self.leftover[self.pos..];
+        let len = available.len().min(buf.remaining());
+        buf.put_slice(&available[..len]);
+        self.pos += len;
+    }
+}
+
+impl<S: EntropySource + Send + Unpin + 'static> AsyncRead for AsyncRdReader<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.fork.forked() {
+            this.pos = this.leftover.len();
+            this.fork.reset();
+        }
+        if this.pos < this.leftover.len() {
+            this.read_leftover(buf);
+            return Poll::Ready(Ok(()));
+        }
+        if let Some(blocking) = this.blocking.as_mut() {
+            let (source, result) = match Pin::new(blocking).poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(completed)) => completed,
+                Poll::Ready(Err(error)) => {
+                    this.blocking = None;
+                    return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, error)));
+                }
+            };
+            this.blocking = None;
+            this.source = Some(source);
+            this.leftover = result?;
+            this.pos = 0;
+            this.read_leftover(buf);
+            return Poll::Ready(Ok(()));
+        }
+        let source = match this.source.as_mut() {
+            Some(source) => source,
+            None => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "the source was lost in a failed blocking task",
+                )))
+            }
+        };
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        if buf.remaining() >= this.blocking_threshold {
+            let mut source = this.source.take().expect("checked above");
+            let len = buf.remaining();
+            this.blocking = Some(tokio::task::spawn_blocking(move || {
+                let mut data = vec![0; len];
+                let result = source.try_fill_bytes(&mut data).map(|()| data);
+                (source, result)
+            }));
+            return Pin::new(this).poll_read(cx, buf);
+        }
+        let produced = source.try_fill_bytes_nonblocking(buf.initialize_unfilled())?;
+        if produced > 0 {
+            buf.advance(produced);
+            this.empty_polls = 0;
+            return Poll::Ready(Ok(()));
+        }
+        this.empty_polls += 1;
+        if this.empty_polls >= MAX_EMPTY_POLLS {
+            this.empty_polls = 0;
+            return Poll::Ready(Err(ErrorCode::HardwareFailure.into()));
+        }
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AsyncRdReader;
+    use crate::mock::{Replay, Trickle};
+    use crate::{EntropySource, ErrorCode};
+    use core::future::Future;
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+    use std::io;
+    use tokio::io::{AsyncRead, ReadBuf};
+    use tokio::runtime::{Builder, Runtime};
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().build().unwrap()
+    }
+
+    /// A single read, as performed by `AsyncReadExt::read`, which needs the `io-util` feature of
+    /// tokio.
+    struct Read<'a, S> {
+        reader: &'a mut AsyncRdReader<S>,
+        buf: &'a mut [u8],
+    }
+
+    impl<S: EntropySource + Send + Unpin + 'static> Future for Read<'_, S> {
+        type Output = io::Result<usize>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+            let this = &mut *self;
+            let mut buf = ReadBuf::new(this.buf);
+            match Pin::new(&mut *this.reader).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) => Poll::Ready(Ok(buf.filled().len())),
+                Poll::Ready(Err(error)) => Poll::Ready(Err(error)),
+                Poll::Pending => Poll::Pending,
+            }
+        }
+    }
+
+    impl<S: EntropySource + Send + Unpin + 'static> AsyncRdReader<S> {
+        fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> Read<'a, S> {
+            Read { reader: self, buf }
+        }
+
+        async fn read_exact(&mut self, mut buf: &mut [u8]) -> io::Result<()> {
+            while !buf.is_empty() {
+                let len = self.read(buf).await?;
+                if len == 0 {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                buf = &mut core::mem::take(&mut buf)[len..];
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn reads_without_blocking() {
+        static INPUT: [u8; 4] = [1, 2, 3, 4];
+        let mut reader = AsyncRdReader::with_source(Trickle(Replay(&INPUT)));
+        runtime().block_on(async {
+            let mut buf = [0; 3];
+            reader.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [1, 2, 3]);
+            assert_eq!(reader.read(&mut buf).await.unwrap(), 1);
+            let error = reader.read(&mut buf).await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::Other);
+            let code = error.get_ref().unwrap().downcast_ref::<ErrorCode>();
+            assert!(matches!(code, Some(ErrorCode::HardwareFailure)));
+        });
+    }
+
+    #[test]
+    fn offloads_large_reads() {
+        static INPUT: [u8; 16] = [7; 16];
+        let mut reader = AsyncRdReader::with_source(Replay(&INPUT));
+        reader.set_blocking_threshold(8);
+        runtime().block_on(async {
+            let mut buf = [0; 10];
+            assert_eq!(reader.read(&mut buf).await.unwrap(), 10);
+            // The rest is read without blocking.
+            assert_eq!(reader.read(&mut buf[..6]).await.unwrap(), 6);
+            // The blocking read reports the failure of the source.
+            assert!(reader.read(&mut buf).await.is_err());
+            assert!(reader.read(&mut buf[..1]).await.is_err());
+        });
+    }
+
+    #[test]
+    fn async_rdreader_works() {
+        let _ = AsyncRdReader::new().map(|mut reader| {
+            runtime().block_on(async {
+                let mut buf = [0; 10_000];
+                reader.read_exact(&mut buf[..100]).await.unwrap();
+                reader.read_exact(&mut buf).await.unwrap();
+            });
+        });
+    }
+}
@@ -0,0 +1,145 @@
+use crate::fork::ForkGuard;
+use crate::{EntropySource, ErrorCode, RdRand};
+
+/// A wrapper around [`RdRand`] (or another [`EntropySource`]) handing out individual random bits.
+///
+/// The wrapper draws a 64-bit word from the source whenever it runs out of bits and hands the bits
+/// of the word out one request at a time, least significant bits first. No bit is ever discarded:
+/// a request for more bits than are left combines the remaining bits with bits of the next word.
+///
+/// Use [`words_consumed`] to find out how many words have been drawn from the source.
+///
+/// [`words_consumed`]: BitSource::words_consumed
+pub struct BitSource<S = RdRand> {
+    source: S,
+    /// The lowest `available` bits of `buffer` have not been handed out yet.
+    buffer: u64,
+    available: u32,
+    words_consumed: u64,
+    fork: ForkGuard,
+}
+
+impl BitSource {
+    /// Create a new bit source drawing from [`RdRand`].
+    ///
+    /// An error is returned if the `rdrand` instruction is not supported.
+    pub fn new() -> Result<Self, ErrorCode> {
+        RdRand::new().map(Self::with_source)
+    }
+}
+
+impl<S: EntropySource> BitSource<S> {
+    /// Create a new bit source drawing from `source`.
+    pub fn with_source(source: S) -> Self {
+        BitSource {
+            source,
+            buffer: 0,
+            available: 0,
+            words_consumed: 0,
+            fork: ForkGuard::new(),
+        }
+    }
+
+    /// Generate `n` random bits, returned in the lowest bits of the result.
+    ///
+    /// The remaining bits of the result are zero. If an error is returned, no bits are consumed.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `n` is greater than 64.
+    pub fn try_next_bits(&mut self, n: u32) -> Result<u64, ErrorCode> {
+        assert!(n <= 64, "cannot generate more than 64 bits at once");
+        if self.fork.forked() {
+            self.buffer = 0;
+            self.available = 0;
+            self.fork.reset();
+        }
+        if n <= self.available {
+            let bits = self.buffer & mask(n);
+            self.buffer = self.buffer.checked_shr(n).unwrap_or(0);
+            self.available -= n;
+            return Ok(bits);
+        }
+        let word = self.source.try_next_u64()?;
+        self.words_consumed += 1;
+        // `self.available < n <= 64`, so none of the shifts below overflow.
+        let low = self.buffer;
+        let taken = self.available;
+        let missing = n - taken;
+        self.buffer = word.checked_shr(missing).unwrap_or(0);
+        self.available = 64 - missing;
+        Ok(low | (word & mask(missing)) << taken)
+    }
+
+    /// Generate a random `bool`, consuming a single bit.
+    pub fn try_next_bool(&mut self) -> Result<bool, ErrorCode> {
+        self.try_next_bits(1).map(|bit| bit == 1)
+    }
+
+    /// The number of 64-bit words drawn from the source so far.
+    pub fn words_consumed(&self) -> u64 {
+        self.words_consumed
+    }
+
+    /// The number of bits left over from the last word drawn from the source.
+    pub fn bits_available(&self) -> u32 {
+        self.available
+    }
+
+    /// Unwrap the source, discarding any bits left over.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+/// A mask of the lowest `n` bits.
+fn mask(n: u32) -> u64 {
+    u64::MAX.checked_shr(64 - n).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::BitSource;
+    use crate::mock::{hex, Replay};
+
+    #[test]
+    fn hands_out_every_bit() {
+        let input = hex("a65a000000000080ff00000000000001");
+        let mut bits = BitSource::with_source(Replay(&input));
+        assert_eq!(bits.try_next_bits(0).unwrap(), 0);
+        assert_eq!(bits.words_consumed(), 0);
+        assert!(!bits.try_next_bool().unwrap());
+        assert_eq!(bits.try_next_bits(3).unwrap(), 0b011);
+        assert_eq!(bits.try_next_bits(12).unwrap(), 0x5aa);
+        assert_eq!(bits.bits_available(), 48);
+        assert_eq!(bits.words_consumed(), 1);
+        // Spans the boundary between the two words.
+        assert_eq!(bits.try_next_bits(56).unwrap(), 0xff_8000_0000_0000);
+        assert_eq!(bits.words_consumed(), 2);
+        assert_eq!(bits.try_next_bits(56).unwrap(), 0x01_0000_0000_0000);
+        assert_eq!(bits.bits_available(), 0);
+        // Failures don't consume anything.
+        assert!(bits.try_next_bits(1).is_err());
+        assert_eq!(bits.words_consumed(), 2);
+    }
+
+    #[test]
+    fn full_words() {
+        let input = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        let mut bits = BitSource::with_source(Replay(&input));
+        assert_eq!(bits.try_next_bits(64).unwrap(), 0x0807_0605_0403_0201);
+        assert_eq!(bits.try_next_bits(4).unwrap(), 0x9);
+        assert_eq!(bits.try_next_bits(60).unwrap(), 0x0100_f0e0_d0c0_b0a0);
+        assert_eq!(bits.words_consumed(), 2);
+    }
+
+    #[test]
+    fn bitsource_works() {
+        let _ = BitSource::new().map(|mut bits| {
+            for n in 0..=64 {
+                bits.try_next_bits(n).unwrap();
+            }
+            assert_eq!(bits.words_consumed(), 33);
+        });
+    }
+}
@@ -0,0 +1,23 @@
+//! AES-CBC-MAC condensing of samples from an [`EntropySource`].
+use crate::{EntropySource, ErrorCode};
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::{Aes128, Block};
+
+/// Condense `samples` 128-bit samples from `source` with AES-128-CBC-MAC under `key`.
+pub(crate) fn condense<S: EntropySource>(
+    key: &[u8; 16],
+    source: &mut S,
+    samples: usize,
+) -> Result<Block, ErrorCode> {
+    let cipher = Aes128::new(key.into());
+    let mut state = Block::default();
+    let mut sample = [0; 16];
+    for _ in 0..samples {
+        source.try_fill_bytes(&mut sample)?;
+        for (s, x) in state.iter_mut().zip(sample.iter()) {
+            *s ^= x;
+        }
+        cipher.encrypt_block(&mut state);
+    }
+    Ok(state)
+}
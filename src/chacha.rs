@@ -0,0 +1,327 @@
+#[cfg(feature = "rand_core_06")]
+use crate::busy_loop_fail;
+use crate::fork::ForkGuard;
+use crate::secret::wipe;
+use crate::{EntropySource, ErrorCode, RdSeed};
+use rand_chacha::rand_core::block::BlockRngCore;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Core;
+#[cfg(feature = "rand_core_06")]
+use rand_core_06::{CryptoRng, Error, RngCore};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// The number of keystream bytes produced by ChaCha20 at once.
+const BLOCK_LEN: usize = 256;
+
+/// The length of the ChaCha20 key.
+const KEY_LEN: usize = 32;
+
+/// The default number of bytes generated before the generator is rekeyed from its source.
+const DEFAULT_REKEY_BYTES: u64 = 1 << 20;
+
+/// A buffered ChaCha20 generator keyed from [`RdSeed`] (or another [`EntropySource`]).
+///
+/// The generator follows the [fast-key-erasure] construction: every time the buffer runs out,
+/// a fresh block of ChaCha20 keystream is generated, the first 32 bytes of which immediately
+/// replace the key and the rest are handed out as output. Output bytes are wiped from the buffer
+/// as they are handed out. Compromise of the generator state thus does not reveal any output
+/// produced earlier. The key and the buffer are wiped when the generator is dropped.
+///
+/// The generator mixes fresh entropy from its source into the key every 1MiB of output. This
+/// budget, as well as an optional time budget, can be adjusted with [`set_rekey_bytes`] and
+/// [`set_rekey_interval`].
+///
+/// This generator produces output at the speed of ChaCha20 in software, which is orders of
+/// magnitude faster than [`RdRand`](crate::RdRand).
+///
+/// This generator is only available with the `chacha` feature enabled.
+///
+/// [fast-key-erasure]: https://blog.cr.yp.to/20170723-random.html
+/// [`set_rekey_bytes`]: ChaChaRdRng::set_rekey_bytes
+/// [`set_rekey_interval`]: ChaChaRdRng::set_rekey_interval
+pub struct ChaChaRdRng<S = RdSeed> {
+    source: S,
+    key: [u8; KEY_LEN],
+    buffer: [u8; BLOCK_LEN],
+    /// The bytes `buffer[index..]` have not been handed out yet.
+    index: usize,
+    rekey_bytes: u64,
+    bytes_since_rekey: u64,
+    #[cfg(feature = "std")]
+    rekey_interval: Option<Duration>,
+    #[cfg(feature = "std")]
+    last_rekey: Instant,
+    fork: ForkGuard,
+}
+
+impl ChaChaRdRng {
+    /// Create a new generator keyed by [`RdSeed`].
+    ///
+    /// An error is returned if the `rdseed` instruction is not supported or if it fails to produce
+    /// the key.
+    pub fn new() -> Result<Self, ErrorCode> {
+        Self::with_source(RdSeed::new()?)
+    }
+}
+
+impl<S: EntropySource> ChaChaRdRng<S> {
+    /// Create a new generator keyed by `source`.
+    pub fn with_source(mut source: S) -> Result<Self, ErrorCode> {
+        let mut key = [0; KEY_LEN];
+        source.try_fill_bytes(&mut key)?;
+        Ok(ChaChaRdRng {
+            source,
+            key,
+            buffer: [0; BLOCK_LEN],
+            index: BLOCK_LEN,
+            rekey_bytes: DEFAULT_REKEY_BYTES,
+            bytes_since_rekey: 0,
+            #[cfg(feature = "std")]
+            rekey_interval: None,
+            #[cfg(feature = "std")]
+            last_rekey: Instant::now(),
+            fork: ForkGuard::new(),
+        })
+    }
+
+    /// Set the number of bytes after which the generator is rekeyed from its source.
+    pub fn set_rekey_bytes(&mut self, bytes: u64) {
+        self.rekey_bytes = bytes;
+    }
+
+    /// Set the time after which the generator is rekeyed from its source.
+    ///
+    /// The time is checked only when the buffer is refilled, so the generator may be used for a
+    /// while longer if it is used sparingly. By default there is no time budget.
+    ///
+    /// This method is only available with the `std` feature enabled.
+    #[cfg(feature = "std")]
+    pub fn set_rekey_interval(&mut self, interval: Option<Duration>) {
+        self.rekey_interval = interval;
+    }
+
+    /// Mix fresh entropy from the source into the key.
+    ///
+    /// Any buffered output is discarded.
+    pub fn rekey(&mut self) -> Result<(), ErrorCode> {
+        let mut fresh = [0; KEY_LEN];
+        self.source.try_fill_bytes(&mut fresh)?;
+        for (k, f) in self.key.iter_mut().zip(fresh.iter()) {
+            *k ^= f;
+        }
+        self.buffer = [0; BLOCK_LEN];
+        self.index = BLOCK_LEN;
+        self.bytes_since_rekey = 0;
+        #[cfg(feature = "std")]
+        {
+            self.last_rekey = Instant::now();
+        }
+        Ok(())
+    }
+
+    fn rekey_due(&self) -> bool {
+        #[cfg(feature = "std")]
+        {
+            if let Some(interval) = self.rekey_interval {
+                if self.last_rekey.elapsed() >= interval {
+                    return true;
+                }
+            }
+        }
+        self.bytes_since_rekey >= self.rekey_bytes
+    }
+
+    fn refill(&mut self) -> Result<(), ErrorCode> {
+        if self.rekey_due() {
+            self.rekey()?;
+        }
+        let mut results = Default::default();
+        ChaCha20Core::from_seed(self.key).generate(&mut results);
+        let words: &[u32] = results.as_ref();
+        for (bytes, word) in self.buffer.chunks_mut(4).zip(words) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        self.key.copy_from_slice(&self.buffer[..KEY_LEN]);
+        self.buffer[..KEY_LEN].iter_mut().for_each(|b| *b = 0);
+        self.index = KEY_LEN;
+        self.bytes_since_rekey += (BLOCK_LEN - KEY_LEN) as u64;
+        Ok(())
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// An error is returned if the generator is due to be rekeyed, but the source fails to produce
+    /// fresh entropy.
+    pub fn try_fill_bytes(&mut self, mut dest: &mut [u8]) -> Result<(), ErrorCode> {
+        if self.fork.forked() {
+            self.rekey()?;
+            self.fork.reset();
+        }
+        while !dest.is_empty() {
+            if self.index == BLOCK_LEN {
+                self.refill()?;
+            }
+            let available = &mut self.buffer[self.index..];
+            let len = available.len().min(dest.len());
+            let (copy_dest, dest_leftover) = { dest }.split_at_mut(len);
+            copy_dest.copy_from_slice(&available[..len]);
+            available[..len].iter_mut().for_each(|b| *b = 0);
+            self.index += len;
+            dest = dest_leftover;
+        }
+        Ok(())
+    }
+}
+
+impl<S: EntropySource> EntropySource for ChaChaRdRng<S> {
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        ChaChaRdRng::try_fill_bytes(self, dest)
+    }
+}
+
+impl<S> Drop for ChaChaRdRng<S> {
+    fn drop(&mut self) {
+        wipe(&mut self.key);
+        wipe(&mut self.buffer);
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl<S: EntropySource> RngCore for ChaChaRdRng<S> {
+    /// Generate a single random `u32` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if the generator is due to be rekeyed and the source fails to
+    /// produce fresh entropy.
+    fn next_u32(&mut self) -> u32 {
+        match EntropySource::try_next_u32(self) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Generate a single random `u64` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if the generator is due to be rekeyed and the source fails to
+    /// produce fresh entropy.
+    fn next_u64(&mut self) -> u64 {
+        match EntropySource::try_next_u64(self) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_fill_bytes` would return an error.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match ChaChaRdRng::try_fill_bytes(self, dest) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        ChaChaRdRng::try_fill_bytes(self, dest).map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl<S: EntropySource + CryptoRng> CryptoRng for ChaChaRdRng<S> {}
+
+#[cfg(feature = "rand_core_09")]
+impl<S: EntropySource> rand_core_09::TryRngCore for ChaChaRdRng<S> {
+    type Error = ErrorCode;
+
+    /// Generate a single random `u32` value.
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        EntropySource::try_next_u32(self)
+    }
+
+    /// Generate a single random `u64` value.
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        EntropySource::try_next_u64(self)
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        ChaChaRdRng::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_09")]
+impl<S: EntropySource + rand_core_09::TryCryptoRng> rand_core_09::TryCryptoRng for ChaChaRdRng<S> {}
+
+#[cfg(test)]
+mod test {
+    use super::{ChaChaRdRng, BLOCK_LEN, KEY_LEN};
+    use crate::mock::Replay;
+    use rand_chacha::rand_core::{RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    fn keystream(key: [u8; KEY_LEN]) -> [u8; BLOCK_LEN] {
+        let mut block = [0; BLOCK_LEN];
+        ChaCha20Rng::from_seed(key).fill_bytes(&mut block);
+        block
+    }
+
+    #[test]
+    fn fast_key_erasure() {
+        let key = [42; KEY_LEN];
+        let mut rng = ChaChaRdRng::with_source(Replay(&key)).unwrap();
+        let first = keystream(key);
+        let mut next_key = [0; KEY_LEN];
+        next_key.copy_from_slice(&first[..KEY_LEN]);
+        let second = keystream(next_key);
+
+        let mut output = [0; 2 * (BLOCK_LEN - KEY_LEN)];
+        rng.try_fill_bytes(&mut output[..5]).unwrap();
+        rng.try_fill_bytes(&mut output[5..]).unwrap();
+        assert_eq!(&output[..BLOCK_LEN - KEY_LEN], &first[KEY_LEN..]);
+        assert_eq!(&output[BLOCK_LEN - KEY_LEN..], &second[KEY_LEN..]);
+        assert!(rng.buffer.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn rekeys_after_budget() {
+        let entropy = [1; 2 * KEY_LEN];
+        let mut rng = ChaChaRdRng::with_source(Replay(&entropy)).unwrap();
+        rng.set_rekey_bytes((BLOCK_LEN - KEY_LEN) as u64);
+        let mut output = [0; BLOCK_LEN - KEY_LEN];
+        rng.try_fill_bytes(&mut output).unwrap();
+        // The rekey consumes the remaining entropy.
+        rng.try_fill_bytes(&mut output).unwrap();
+        // The source has ran out by the next rekey.
+        assert!(rng.try_fill_bytes(&mut output).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn rekeys_after_interval() {
+        let entropy = [1; 2 * KEY_LEN];
+        let mut rng = ChaChaRdRng::with_source(Replay(&entropy)).unwrap();
+        rng.set_rekey_interval(Some(std::time::Duration::from_secs(0)));
+        let mut output = [0; 1];
+        rng.try_fill_bytes(&mut output).unwrap();
+        assert!(rng.try_fill_bytes(&mut [0; BLOCK_LEN]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "rand_core_06")]
+    fn chacha_rdrng_works() {
+        let _ = ChaChaRdRng::new().map(|mut r| {
+            let mut buffer = [0; 1000];
+            r.fill_bytes(&mut buffer);
+            r.next_u64();
+            r.rekey().unwrap();
+            r.next_u32();
+        });
+    }
+}
@@ -1,6 +1,89 @@
 //! Project changelog
 
 
+/// ## Breaking changes
+///
+/// * The dependency on `rand_core` 0.6 is now optional and enabled by the default `rand_core_06`
+///   feature. The `RngCore` and `CryptoRng` implementations, the conversions between `ErrorCode`
+///   and `rand_core::Error`, `RdSeed::seed` and `Reseeding` require this feature.
+/// * rustc version 1.60 is now required to build the library (up from 1.46). The current releases
+///   of the dependencies of several features, including the default ones on Unix, require newer
+///   compilers, as listed in the crate documentation.
+///
+/// ## Additions
+///
+/// * The `drbg` module implementing the `HMAC_DRBG` and `Hash_DRBG` mechanisms from NIST
+///   SP 800-90A with `RdSeed` as the default source of entropy input. It is available with the
+///   `drbg` feature enabled.
+/// * The `EntropySource` trait implemented by `RdRand` and `RdSeed`. Implementations only need to
+///   provide `try_fill_bytes`.
+/// * `SeedFromRdRand`, a seed-grade generator for processors that support `rdrand`, but not
+///   `rdseed`. It is available with the `seed-from-rdrand` feature enabled.
+/// * `ChaChaRdRng`, a fast-key-erasure ChaCha20 generator that is periodically rekeyed from
+///   `RdSeed`. It is available with the `chacha` feature enabled.
+/// * `RdSeed::seed` to seed any `SeedableRng`.
+/// * `Reseeding`, a wrapper that periodically reseeds any block generator from `RdSeed` and
+///   tolerates `rdseed` failures.
+/// * `Fortuna`, an entropy accumulator that combines `RdSeed`, `RdRand`, the operating system's
+///   random number generator and user-supplied events. It is available with the `fortuna` feature
+///   enabled.
+/// * `Conditioned`, a source of seed material conditioned with SHA-256 `Hash_df` or AES-CBC-MAC
+///   from a configurable surplus of `RdSeed` output. It is available with the `conditioned`
+///   feature enabled.
+/// * The `rand_core_09` feature implementing `TryRngCore` and `TryCryptoRng` from `rand_core` 0.9
+///   with `ErrorCode` as the error type for all generators in this crate.
+/// * The `register_getrandom_backend!` macro registering `RdRand` (or another generator) as the
+///   custom backend of `getrandom` 0.3. It is available with the `getrandom_03` feature enabled.
+/// * `RdReader`, an adapter implementing `io::Read` and `io::BufRead`, and the conversion from
+///   `ErrorCode` to `io::Error`. They are available with the `std` feature enabled.
+/// * `EntropySource::try_fill_bytes_nonblocking`, which fills a buffer partially instead of
+///   retrying when the hardware runs out of entropy.
+/// * `AsyncRdReader`, an adapter implementing `tokio::io::AsyncRead` that yields to the executor
+///   when `RdSeed` runs out of entropy and performs large reads on the blocking thread pool. It is
+///   available with the `tokio` feature enabled.
+/// * `RdStream`, a `futures::Stream` of fixed-size blocks of random data that yields to the
+///   executor when `RdSeed` runs out of entropy. It is available with the `futures` feature
+///   enabled.
+/// * `try_fill_u16`, `try_fill_u32`, `try_fill_u64` and `try_fill_u128` to fill slices of integers
+///   with one instruction result per element, and their panicking counterparts `fill_u16`,
+///   `fill_u32`, `fill_u64` and `fill_u128`.
+/// * `try_fill` to fill slices of any `bytemuck::Pod` type. It is available with the `bytemuck`
+///   feature enabled.
+/// * `try_fill_uninit` to fill buffers of `MaybeUninit<u8>` without initializing them first.
+/// * `try_next_array` to generate arrays of random bytes by value, and `try_next_u128`.
+/// * `try_fill_bytes_vectored` to fill several buffers without discarding the leftover bytes of a
+///   word between them. It is available with the `std` feature enabled.
+/// * `BitSource`, a wrapper handing out individual random bits without discarding any.
+/// * `try_gen_range_u32` and `try_gen_range_u64` to sample integers from ranges without bias.
+/// * `try_next_f64` and `try_next_f32` to generate floating-point numbers in `[0, 1)` or `(0, 1]`
+///   with the standard or full precision, as selected by `FloatMode`.
+/// * `try_shuffle`, `try_choose` and `try_sample_indices` to shuffle and sample sequences without
+///   bias directly from the hardware generator. `try_sample_indices` is available with the `std`
+///   feature enabled.
+/// * `try_uuid_v4` and `try_uuid_v7` to generate UUIDs as specified by RFC 9562 from `RdRand` and
+///   `RdSeed`, and `try_new_uuid_v4` and `try_new_uuid_v7` returning `uuid::Uuid`, which are
+///   available with the `uuid` feature enabled. `uuid_v4` and `uuid_v7` generate the same UUIDs
+///   from any `EntropySource`.
+/// * `TokenGenerator`, a generator of random tokens over an `Alphabet` without modulo bias, with
+///   optional Luhn mod N check characters and grouping. It is available with the `std` feature
+///   enabled.
+/// * `SecretBytes` and `SecretVec`, buffers of secret random bytes that are wiped when dropped,
+///   with redacted `Debug` output and constant-time comparisons. `SecretVec` is available with the
+///   `std` feature enabled, and both implement `Zeroize` with the `zeroize` feature enabled.
+/// * The partial words used to fill byte buffers are now wiped after use.
+/// * `LockedSecret`, secret random bytes in `mlock`ed memory that is excluded from core dumps and
+///   surrounded by guard pages. It is available on Linux with the `locked` feature enabled.
+/// * `global` and `RdRand::shared`, returning a `&'static` handle to a shared generator after
+///   detecting support for the instruction once, and the free functions `fill` and `u64` drawing
+///   from it. `register_getrandom_backend!` uses this handle by default.
+/// * `thread_rdrand`, returning a `ThreadRdRand` handle that serves small draws from a per-thread
+///   buffer of `RdRand` output and wipes the bytes it hands out. It is available with the `std`
+///   feature enabled.
+/// * The generators and adapters that buffer output or keep state, such as `ThreadRdRand`,
+///   `ChaChaRdRng`, the DRBGs, `Fortuna`, `Reseeding` and `RdReader`, detect forks on Unix with
+///   the `std` feature enabled and discard their buffers and reseed in the child process.
+pub mod unreleased {}
+
 /// Fix the implementation of `try_fill_bytes` when the buffer is aligned but the size is not a
 /// multiple of a word size.
 pub mod r0_8_2 {}
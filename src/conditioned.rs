@@ -0,0 +1,304 @@
+#[cfg(feature = "rand_core_06")]
+use crate::busy_loop_fail;
+use crate::cbc_mac;
+use crate::{EntropySource, ErrorCode, RdSeed};
+#[cfg(feature = "rand_core_06")]
+use rand_core_06::{CryptoRng, Error, RngCore};
+use sha2::{Digest, Sha256};
+
+/// The largest block of output produced by a single conditioning step.
+const MAX_BLOCK_LEN: usize = 32;
+
+/// The default ratio between the number of bytes read from the source and the number of bytes
+/// produced.
+const DEFAULT_SURPLUS: usize = 2;
+
+/// The vetted conditioning components available to [`Conditioned`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Conditioner {
+    /// The `Hash_df` derivation function (SP 800-90A, section 10.3.1) using SHA-256, producing 256
+    /// bits at a time.
+    HashDf,
+    /// The `BCC` function (SP 800-90A, section 10.3.3), i.e. AES-128-CBC-MAC, producing 128 bits at
+    /// a time. The key is drawn from the source when the generator is created.
+    CbcMac,
+}
+
+enum Extractor {
+    HashDf,
+    CbcMac([u8; 16]),
+}
+
+/// A source of conditioned seed material obtained from [`RdSeed`] (or another
+/// [`EntropySource`]).
+///
+/// Every block of output is derived by passing more data from the source than the block holds
+/// (twice as much by default, see [`set_surplus`]) through an approved conditioning component
+/// (SP 800-90B, section 3.1.5.1.1), so this generator can sit between the entropy source and a
+/// DRBG where such a component is required.
+///
+/// The API mirrors that of [`RdSeed`], so this generator can be used in its stead. Since every
+/// method call conditions at least one full block, prefer `try_fill_bytes` over the methods
+/// generating a single integer.
+///
+/// This generator is only available with the `conditioned` feature enabled.
+///
+/// [`set_surplus`]: Conditioned::set_surplus
+pub struct Conditioned<S = RdSeed> {
+    source: S,
+    extractor: Extractor,
+    surplus: usize,
+}
+
+impl Conditioned {
+    /// Create a new generator conditioning the output of [`RdSeed`] with `conditioner`.
+    ///
+    /// An error is returned if the `rdseed` instruction is not supported or, for
+    /// [`Conditioner::CbcMac`], if it fails to produce the key.
+    pub fn new(conditioner: Conditioner) -> Result<Self, ErrorCode> {
+        Self::with_source(RdSeed::new()?, conditioner)
+    }
+}
+
+impl<S: EntropySource> Conditioned<S> {
+    /// Create a new generator conditioning the output of `source` with `conditioner`.
+    pub fn with_source(mut source: S, conditioner: Conditioner) -> Result<Self, ErrorCode> {
+        let extractor = match conditioner {
+            Conditioner::HashDf => Extractor::HashDf,
+            Conditioner::CbcMac => {
+                let mut key = [0; 16];
+                source.try_fill_bytes(&mut key)?;
+                Extractor::CbcMac(key)
+            }
+        };
+        Ok(Conditioned {
+            source,
+            extractor,
+            surplus: DEFAULT_SURPLUS,
+        })
+    }
+
+    /// Set the number of bytes read from the source for every byte of output.
+    ///
+    /// The default is 2.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if `surplus` is zero.
+    pub fn set_surplus(&mut self, surplus: usize) {
+        assert!(surplus > 0, "surplus must be positive");
+        self.surplus = surplus;
+    }
+
+    /// The conditioning component used by this generator.
+    pub fn conditioner(&self) -> Conditioner {
+        match self.extractor {
+            Extractor::HashDf => Conditioner::HashDf,
+            Extractor::CbcMac(_) => Conditioner::CbcMac,
+        }
+    }
+
+    /// Generate a single random `u16` value.
+    ///
+    /// In case `Err` is returned, the caller should assume that a non-recoverable failure has
+    /// occured and use another random number genrator instead.
+    pub fn try_next_u16(&mut self) -> Result<u16, ErrorCode> {
+        EntropySource::try_next_u16(self)
+    }
+
+    /// Generate a single random `u32` value.
+    ///
+    /// In case `Err` is returned, the caller should assume that a non-recoverable failure has
+    /// occured and use another random number genrator instead.
+    pub fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        EntropySource::try_next_u32(self)
+    }
+
+    /// Generate a single random `u64` value.
+    ///
+    /// In case `Err` is returned, the caller should assume that a non-recoverable failure has
+    /// occured and use another random number genrator instead.
+    pub fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        EntropySource::try_next_u64(self)
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// If an error is returned, the caller should assume that an non-recoverable hardware failure
+    /// has occured and use another random number genrator instead.
+    pub fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        let block_len = self.block_len();
+        for chunk in dest.chunks_mut(block_len) {
+            let block = self.try_next_block()?;
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        Ok(())
+    }
+
+    fn block_len(&self) -> usize {
+        match self.extractor {
+            Extractor::HashDf => 32,
+            Extractor::CbcMac(_) => 16,
+        }
+    }
+
+    /// Condense `surplus` blocks worth of source output into a single block.
+    fn try_next_block(&mut self) -> Result<[u8; MAX_BLOCK_LEN], ErrorCode> {
+        let mut output = [0; MAX_BLOCK_LEN];
+        match self.extractor {
+            Extractor::HashDf => {
+                let mut hash = Sha256::new();
+                hash.update([0x01]);
+                hash.update(256u32.to_be_bytes());
+                let mut sample = [0; 32];
+                for _ in 0..self.surplus {
+                    self.source.try_fill_bytes(&mut sample)?;
+                    hash.update(sample);
+                }
+                output.copy_from_slice(&hash.finalize());
+            }
+            Extractor::CbcMac(key) => {
+                let state = cbc_mac::condense(&key, &mut self.source, self.surplus)?;
+                output[..16].copy_from_slice(&state);
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl<S: EntropySource> EntropySource for Conditioned<S> {
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        Conditioned::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl<S: EntropySource> RngCore for Conditioned<S> {
+    /// Generate a single random `u32` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_next_u32` would return an error.
+    fn next_u32(&mut self) -> u32 {
+        match self.try_next_u32() {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Generate a single random `u64` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_next_u64` would return an error.
+    fn next_u64(&mut self) -> u64 {
+        match self.try_next_u64() {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_fill_bytes` would return an error.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match Conditioned::try_fill_bytes(self, dest) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        Conditioned::try_fill_bytes(self, dest).map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl<S: EntropySource + CryptoRng> CryptoRng for Conditioned<S> {}
+
+#[cfg(feature = "rand_core_09")]
+impl<S: EntropySource> rand_core_09::TryRngCore for Conditioned<S> {
+    type Error = ErrorCode;
+
+    /// Generate a single random `u32` value.
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        Conditioned::try_next_u32(self)
+    }
+
+    /// Generate a single random `u64` value.
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        Conditioned::try_next_u64(self)
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        Conditioned::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_09")]
+impl<S: EntropySource + rand_core_09::TryCryptoRng> rand_core_09::TryCryptoRng for Conditioned<S> {}
+
+#[cfg(test)]
+mod test {
+    use super::{Conditioned, Conditioner};
+    use crate::mock::{hex, Replay};
+
+    fn counting() -> [u8; 128] {
+        let mut input = [0; 128];
+        for (i, b) in input.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        input
+    }
+
+    // The expected outputs were computed with an independent implementation on top of Python's
+    // hashlib and the AES-128 of OpenSSL.
+
+    #[test]
+    fn hash_df() {
+        let input = counting();
+        let mut rng = Conditioned::with_source(Replay(&input), Conditioner::HashDf).unwrap();
+        let mut output = [0; 40];
+        rng.try_fill_bytes(&mut output).unwrap();
+        let expected = hex(concat!(
+            "3773bc0b6616dc4a0089bd3ce83587341e5d65572df3004478d7d361da35858f",
+            "25741d993babb128"
+        ));
+        assert_eq!(&output[..], &expected[..]);
+        assert!(rng.try_fill_bytes(&mut [0; 1]).is_err());
+    }
+
+    #[test]
+    fn cbc_mac() {
+        let input = counting();
+        let mut rng = Conditioned::with_source(Replay(&input[..48]), Conditioner::CbcMac).unwrap();
+        assert_eq!(rng.conditioner(), Conditioner::CbcMac);
+        let mut output = [0; 16];
+        rng.try_fill_bytes(&mut output).unwrap();
+        assert_eq!(&output[..], &hex("138bb0fe942520a0013f42531dd5022c")[..]);
+    }
+
+    #[test]
+    fn surplus() {
+        let input = [0; 96];
+        let mut rng = Conditioned::with_source(Replay(&input), Conditioner::HashDf).unwrap();
+        rng.set_surplus(3);
+        rng.try_fill_bytes(&mut [0; 32]).unwrap();
+        assert!(rng.try_fill_bytes(&mut [0; 1]).is_err());
+    }
+
+    #[test]
+    fn conditioned_works() {
+        for &conditioner in &[Conditioner::HashDf, Conditioner::CbcMac] {
+            let _ = Conditioned::new(conditioner).map(|mut r| {
+                let mut buffer = [0; 33];
+                r.try_next_u64().unwrap();
+                r.try_fill_bytes(&mut buffer).unwrap();
+            });
+        }
+    }
+}
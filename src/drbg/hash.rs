@@ -0,0 +1,216 @@
+use super::{Mechanism, MAX_BYTES_PER_REQUEST, RESEED_INTERVAL};
+use crate::secret::wipe;
+use core::marker::PhantomData;
+use sha2::Digest;
+
+/// The largest `seedlen` of the approved hash functions, in bytes.
+const MAX_SEED_LEN: usize = 888 / 8;
+
+/// The `Hash_DRBG` mechanism (SP 800-90A, section 10.1.1) using the hash function `D`.
+pub struct HashDrbg<D: Digest> {
+    value: [u8; MAX_SEED_LEN],
+    constant: [u8; MAX_SEED_LEN],
+    reseed_counter: u64,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> HashDrbg<D> {
+    /// The `seedlen` of the hash function, in bytes.
+    fn seed_len() -> usize {
+        if <D as Digest>::output_size() <= 32 {
+            440 / 8
+        } else {
+            888 / 8
+        }
+    }
+
+    /// The Hash_df function. `input_string` is the concatenation of the slices.
+    fn hash_df(input_string: &[&[u8]], dest: &mut [u8]) {
+        let no_of_bits = (dest.len() as u32 * 8).to_be_bytes();
+        for (counter, chunk) in dest.chunks_mut(<D as Digest>::output_size()).enumerate() {
+            let mut hash = D::new();
+            hash.update([counter as u8 + 1]);
+            hash.update(no_of_bits);
+            for input in input_string {
+                hash.update(input);
+            }
+            chunk.copy_from_slice(&hash.finalize()[..chunk.len()]);
+        }
+    }
+
+    /// Add the big-endian number `addend` to the big-endian number `dest`, modulo
+    /// 2^(8 * dest.len()).
+    fn add(dest: &mut [u8], addend: &[u8]) {
+        let mut carry = 0u16;
+        let mut addend = addend.iter().rev();
+        for byte in dest.iter_mut().rev() {
+            let sum = u16::from(*byte) + u16::from(*addend.next().unwrap_or(&0)) + carry;
+            *byte = sum as u8;
+            carry = sum >> 8;
+        }
+    }
+
+    fn derive_constant(&mut self) {
+        let len = Self::seed_len();
+        wipe(&mut self.constant);
+        Self::hash_df(&[&[0x00], &self.value[..len]], &mut self.constant[..len]);
+        self.reseed_counter = 1;
+    }
+}
+
+impl<D: Digest> Mechanism for HashDrbg<D> {
+    fn security_strength() -> usize {
+        match <D as Digest>::output_size() {
+            0..=20 => 16,
+            21..=28 => 24,
+            _ => 32,
+        }
+    }
+
+    fn instantiate(entropy_input: &[u8], nonce: &[u8], personalization_string: &[u8]) -> Self {
+        let mut drbg = HashDrbg {
+            value: [0; MAX_SEED_LEN],
+            constant: [0; MAX_SEED_LEN],
+            reseed_counter: 1,
+            digest: PhantomData,
+        };
+        Self::hash_df(
+            &[entropy_input, nonce, personalization_string],
+            &mut drbg.value[..Self::seed_len()],
+        );
+        drbg.derive_constant();
+        drbg
+    }
+
+    fn reseed(&mut self, entropy_input: &[u8], additional_input: &[u8]) {
+        let len = Self::seed_len();
+        let mut value = [0; MAX_SEED_LEN];
+        Self::hash_df(
+            &[&[0x01], &self.value[..len], entropy_input, additional_input],
+            &mut value[..len],
+        );
+        wipe(&mut self.value);
+        self.value = value;
+        wipe(&mut value);
+        self.derive_constant();
+    }
+
+    fn reseed_required(&self) -> bool {
+        self.reseed_counter > RESEED_INTERVAL
+    }
+
+    fn generate(&mut self, dest: &mut [u8], additional_input: &[u8]) {
+        assert!(dest.len() <= MAX_BYTES_PER_REQUEST, "request too long");
+        assert!(!self.reseed_required(), "reseed required");
+        let len = Self::seed_len();
+        if !additional_input.is_empty() {
+            let w = D::new()
+                .chain_update([0x02])
+                .chain_update(&self.value[..len])
+                .chain_update(additional_input)
+                .finalize();
+            Self::add(&mut self.value[..len], &w);
+        }
+
+        // Hashgen
+        let mut data = self.value;
+        for chunk in dest.chunks_mut(<D as Digest>::output_size()) {
+            chunk.copy_from_slice(&D::digest(&data[..len])[..chunk.len()]);
+            Self::add(&mut data[..len], &[0x01]);
+        }
+        wipe(&mut data);
+
+        let h = D::new()
+            .chain_update([0x03])
+            .chain_update(&self.value[..len])
+            .finalize();
+        Self::add(&mut self.value[..len], &h);
+        Self::add(&mut self.value[..len], &self.constant[..len]);
+        Self::add(&mut self.value[..len], &self.reseed_counter.to_be_bytes());
+        self.reseed_counter += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::HashDrbg;
+    use crate::drbg::Mechanism;
+    use crate::mock::hex;
+    use sha2::{Sha256, Sha512};
+
+    // The first entry of each list is the first NIST CAVS 14.3 Hash_DRBG vector without reseeding
+    // or prediction resistance. The other entries are known answers computed by an independent
+    // implementation of SP 800-90A for the inputs of the CAVS 14.3 HMAC_DRBG vectors with
+    // personalization string and additional input. The entropy input strings are the
+    // concatenation of EntropyInput and Nonce.
+    const SHA256: &[(&str, &str, &str, &str, &str)] = &[
+        (
+            "a65ad0f345db4e0effe875c3a2e71f42c7129d620ff5c119a9ef55f05185e0fb8581f9317517276e06e9607ddbcbcc2e",
+            "",
+            "",
+            "",
+            "d3e160c35b99f340b2628264d1751060e0045da383ff57a57d73a673d2b8d80daaf6a6c35a91bb4579d73fd0c8fed111b0391306828adfed528f018121b3febdc343e797b87dbb63db1333ded9d1ece177cfa6b71fe8ab1da46624ed6415e51ccde2c7ca86e283990eeaeb91120415528b2295910281b02dd431f4c9f70427df",
+        ),
+        (
+            "ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488659ba96c601dc69fc902940805ec0ca8",
+            "",
+            "",
+            "",
+            "b3638df4d83a677888b3368b6e8495fbe46ffc657541aa1d2499725316db4b7314ec576e318088e839c4fdbc6c932d5311b307066d5f4fe92bd1a2e0f5d3f5c7d73849a8eb30bc1306077ba87faa8d4341d594f8f66279e066f05295bf842a9b25ab8ebee9197124cb8dbcb6f22220e089b0768f06300db7fd8d3dc378ef1ca2",
+        ),
+        (
+            "5d3286bc53a258a53ba781e2c4dcd79a790e43bbe0e89fb3eed39086be34174bc5422294b7318952ace7055ab7570abf",
+            "2dba094d008e150d51c4135bb2f03dcde9cbf3468a12908a1b025c120c985b9d",
+            "793a7ef8f6f0482beac542bb785c10f8b7b406a4de92667ab168ecc2cf7573c6",
+            "2238cdb4e23d629fe0c2a83dd8d5144ce1a6229ef41dabe2a99ff722e510b530",
+            "cb8cfdc30a09986aab61148c8238f1b6bf04a863182042301866ec9c21bde8e8cdd952bf63af88e11704fbcc71cba2618688b252aabb478a85442bbfd2ca7afa0aa9fcafd5461750ebacd978093b71b4f9f016fcaf41d7dc22cfe67f7cb70f87b6827a425aac2c55baccb2edee3d310e5e3b550d36715a1a6aab0666544e96ca",
+        ),
+    ];
+
+    const SHA512: &[(&str, &str, &str, &str, &str)] = &[
+        (
+            "6b50a7d8f8a55d7a3df8bb40bcc3b722d8708de67fda010b03c4c84d72096f8c3ec649cc6256d9fa31db7a2904aaf025",
+            "",
+            "",
+            "",
+            "95b7f17e9802d3577392c6a9c08083b67dd1292265b5f42d237f1c55bb9b10bfcfd82c77a378b8266a0099143b3c2d64611eeeb69acdc055957c139e8b190c7a06955f2c797c2778de940396a501f40e91396acf8d7e45ebdbb53bbf8c975230d2f0ff9106c76119ae498e7fbc03d90f8e4c51627aed5c8d4263d5d2b978873a0de596ee6dc7f7c29e37eee8b34c90dd1cf6a9ddb22b4cbd086b14b35de93da2d5cb1806698cbd7bbb67bfe3d31fd2d1dbd2a1e058a3eb99d7e51f1a938eed5e1c1de23a6b4345d3191409f92f39b3670d8dbfb635d8e6a36932d81033d1448d63b403ddf88e121b6e819ac381226c1321e4b08644f6727c368c5a9f7a4b3ee2",
+        ),
+        (
+        "e97a4631d0a08d549cde8af9a1aae058e3e9585575a726c76a27bc62bed18a4b227221d5fe5a5db9810f9afe56a3ee78",
+        "94084b11d55e0f9c2ef577741753af66ad7a25b28524b50ea970105c3545e97d",
+        "24c81d4773938371b906cf4801957ac22f87432b9c8a84bc5ac04ad5b1cc3f57",
+        "c8c878451e2b76577c36393ca253888c1038885bbfdacd8539615a611e2ac00b",
+        "e4e48756038fc9a16d0afd7e9a34abea033d5338eecb40f7e4de34a371f9b5f97e8acc8bd1a0410dc74d41fc509e6c238d39905ee4b5e021f4f47475b463ec3aa7f19911e6d115264cf93c01c22e3a033aebc2e34391b46b46b68c4232243a2001a612ff3fb6a096b2d61b87a4c8edb38ca02c4bf224ce33c0deb43d28ae3f5136fac03bc6a94990afba3d763961cf9531891dd3d9f2e8c96f57d31d5d14be57a880ac7c80ba7d93705d3983dda10f6c3a381f1030586ed365c9d011c42ba57d7a03049da3b5e95dfa2cafde531a1bd2827954d45058f206babb0ac28ee8de2165f3aaa46b233248247bf47b2ba587010c8a663ec579130fbbdd9547298ad6e7",
+    )];
+
+    fn check<M: Mechanism>(vectors: &[(&str, &str, &str, &str, &str)], output: &mut [u8]) {
+        for &(entropy, pers, add1, add2, expected) in vectors {
+            let entropy = hex(entropy);
+            let (entropy_input, nonce) = entropy.split_at(32);
+            let mut drbg = M::instantiate(entropy_input, nonce, &hex(pers));
+            drbg.generate(output, &hex(add1));
+            drbg.generate(output, &hex(add2));
+            assert_eq!(&output[..], &hex(expected)[..]);
+        }
+    }
+
+    #[test]
+    fn sha256() {
+        check::<HashDrbg<Sha256>>(SHA256, &mut [0; 128]);
+    }
+
+    #[test]
+    fn sha512() {
+        check::<HashDrbg<Sha512>>(SHA512, &mut [0; 256]);
+    }
+
+    #[test]
+    fn add_wraps_around() {
+        let mut value = [0xFF; 4];
+        HashDrbg::<Sha256>::add(&mut value, &[0x01]);
+        assert_eq!(value, [0; 4]);
+        let mut value = [0x00, 0xFF, 0xFF, 0xFE];
+        HashDrbg::<Sha256>::add(&mut value, &[0x01, 0x03]);
+        assert_eq!(value, [0x01, 0x00, 0x01, 0x01]);
+    }
+}
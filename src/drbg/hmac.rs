@@ -0,0 +1,148 @@
+use super::{Mechanism, MAX_BYTES_PER_REQUEST, RESEED_INTERVAL};
+use hmac::digest::{core_api::BlockSizeUser, Digest, KeyInit, Mac, Output};
+use hmac::SimpleHmac;
+
+/// The `HMAC_DRBG` mechanism (SP 800-90A, section 10.1.2) using the hash function `D`.
+pub struct HmacDrbg<D: Digest + BlockSizeUser> {
+    key: Output<D>,
+    value: Output<D>,
+    reseed_counter: u64,
+}
+
+impl<D: Digest + BlockSizeUser> HmacDrbg<D> {
+    fn mac(key: &[u8]) -> SimpleHmac<D> {
+        <SimpleHmac<D> as KeyInit>::new_from_slice(key).expect("HMAC accepts keys of any length")
+    }
+
+    /// The HMAC_DRBG_Update function. `provided_data` is the concatenation of the slices.
+    fn update(&mut self, provided_data: &[&[u8]]) {
+        for &separator in &[0x00u8, 0x01] {
+            let mut mac = Self::mac(&self.key);
+            mac.update(&self.value);
+            mac.update(&[separator]);
+            for data in provided_data {
+                mac.update(data);
+            }
+            self.key = mac.finalize().into_bytes();
+            self.value = Self::mac(&self.key)
+                .chain_update(&self.value)
+                .finalize()
+                .into_bytes();
+            if provided_data.iter().all(|data| data.is_empty()) {
+                break;
+            }
+        }
+    }
+}
+
+impl<D: Digest + BlockSizeUser> Mechanism for HmacDrbg<D> {
+    fn security_strength() -> usize {
+        match <D as Digest>::output_size() {
+            0..=20 => 16,
+            21..=28 => 24,
+            _ => 32,
+        }
+    }
+
+    fn instantiate(entropy_input: &[u8], nonce: &[u8], personalization_string: &[u8]) -> Self {
+        let mut drbg = HmacDrbg {
+            key: Output::<D>::default(),
+            value: Output::<D>::default(),
+            reseed_counter: 1,
+        };
+        drbg.value.iter_mut().for_each(|b| *b = 0x01);
+        drbg.update(&[entropy_input, nonce, personalization_string]);
+        drbg
+    }
+
+    fn reseed(&mut self, entropy_input: &[u8], additional_input: &[u8]) {
+        self.update(&[entropy_input, additional_input]);
+        self.reseed_counter = 1;
+    }
+
+    fn reseed_required(&self) -> bool {
+        self.reseed_counter > RESEED_INTERVAL
+    }
+
+    fn generate(&mut self, dest: &mut [u8], additional_input: &[u8]) {
+        assert!(dest.len() <= MAX_BYTES_PER_REQUEST, "request too long");
+        assert!(!self.reseed_required(), "reseed required");
+        if !additional_input.is_empty() {
+            self.update(&[additional_input]);
+        }
+        for chunk in dest.chunks_mut(<D as Digest>::output_size()) {
+            self.value = Self::mac(&self.key)
+                .chain_update(&self.value)
+                .finalize()
+                .into_bytes();
+            chunk.copy_from_slice(&self.value[..chunk.len()]);
+        }
+        self.update(&[additional_input]);
+        self.reseed_counter += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::HmacDrbg;
+    use crate::drbg::Mechanism;
+    use crate::mock::hex;
+    use sha2::{Sha256, Sha512};
+
+    // HMAC_DRBG test vectors from NIST CAVS 14.3 without reseeding. The entropy input strings are
+    // the concatenation of EntropyInput and Nonce.
+    const SHA256_NO_RESEED: &[(&str, &str, &str, &str, &str)] = &[
+        (
+            "ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488659ba96c601dc69fc902940805ec0ca8",
+            "",
+            "",
+            "",
+            "e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc107694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8",
+        ),
+        (
+            "5d3286bc53a258a53ba781e2c4dcd79a790e43bbe0e89fb3eed39086be34174bc5422294b7318952ace7055ab7570abf",
+            "2dba094d008e150d51c4135bb2f03dcde9cbf3468a12908a1b025c120c985b9d",
+            "793a7ef8f6f0482beac542bb785c10f8b7b406a4de92667ab168ecc2cf7573c6",
+            "2238cdb4e23d629fe0c2a83dd8d5144ce1a6229ef41dabe2a99ff722e510b530",
+            "d04678198ae7e1aeb435b45291458ffde0891560748b43330eaf866b5a6385e74c6fa5a5a44bdb284d436e98d244018d6acedcdfa2e9f499d8089e4db86ae89a6ab2d19cb705e2f048f97fb597f04106a1fa6a1416ad3d859118e079a0c319eb95686f4cbcce3b5101c7a0b010ef029c4ef6d06cdfac97efb9773891688c37cf",
+        ),
+    ];
+
+    const SHA512_NO_RESEED: &[(&str, &str, &str, &str, &str)] = &[
+        (
+            "35049f389a33c0ecb1293238fd951f8ffd517dfde06041d32945b3e26914ba15f7328760be6168e6aa9fb54784989a11",
+            "",
+            "",
+            "",
+            "e76491b0260aacfded01ad39fbf1a66a88284caa5123368a2ad9330ee48335e3c9c9ba90e6cbc9429962d60c1a6661edcfaa31d972b8264b9d4562cf18494128a092c17a8da6f3113e8a7edfcd4427082bd390675e9662408144971717303d8dc352c9e8b95e7f35fa2ac9f549b292bc7c4bc7f01ee0a577859ef6e82d79ef23892d167c140d22aac32b64ccdfeee2730528a38763b24227f91ac3ffe47fb11538e435307e77481802b0f613f370ffb0dbeab774fe1efbb1a80d01154a9459e73ad361108bbc86b0914f095136cbe634555ce0bb263618dc5c367291ce0825518987154fe9ecb052b3f0a256fcc30cc14572531c9628973639beda456f2bddf6",
+        ),
+        (
+            "e97a4631d0a08d549cde8af9a1aae058e3e9585575a726c76a27bc62bed18a4b227221d5fe5a5db9810f9afe56a3ee78",
+            "94084b11d55e0f9c2ef577741753af66ad7a25b28524b50ea970105c3545e97d",
+            "24c81d4773938371b906cf4801957ac22f87432b9c8a84bc5ac04ad5b1cc3f57",
+            "c8c878451e2b76577c36393ca253888c1038885bbfdacd8539615a611e2ac00b",
+            "761422dea283262998c0ffffefc77de2d395c818b9cf1ac2bcd1153235e0d8b63199c51e195135a75f1f87b454484ecc560c532c7ba5923c9490a423c177453459d81efc38ce2939226043cb733062eae303a009b48ee0cf3c7e40abe2b57a70a6062c669a9fbff20b4c94b4ecbc5f744a80d7be8134359581d441da921737b1329470b214f3e679fb7ad48baf046bac59a36b5770806cdef28cc4a8fd0e049b924c3c9216e00ba63c2ff771d66b7520dd33a85382a84b622717e594e447c919926a5b2e94d490ee626da9df587fed674067917963fd51d383e55730c17a124555e2e46e1395c9920d07dae4d67ffee5c759b6a326eec6d7b3ba6dee012e4807",
+        ),
+    ];
+
+    fn check<M: Mechanism>(vectors: &[(&str, &str, &str, &str, &str)], output: &mut [u8]) {
+        for &(entropy, pers, add1, add2, expected) in vectors {
+            let entropy = hex(entropy);
+            let (entropy_input, nonce) = entropy.split_at(32);
+            let mut drbg = M::instantiate(entropy_input, nonce, &hex(pers));
+            drbg.generate(output, &hex(add1));
+            drbg.generate(output, &hex(add2));
+            assert_eq!(&output[..], &hex(expected)[..]);
+        }
+    }
+
+    #[test]
+    fn sha256_no_reseed() {
+        check::<HmacDrbg<Sha256>>(SHA256_NO_RESEED, &mut [0; 128]);
+    }
+
+    #[test]
+    fn sha512_no_reseed() {
+        check::<HmacDrbg<Sha512>>(SHA512_NO_RESEED, &mut [0; 256]);
+    }
+}
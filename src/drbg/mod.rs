@@ -0,0 +1,411 @@
+//! Deterministic random bit generators as specified by [NIST SP 800-90A].
+//!
+//! This module implements the `HMAC_DRBG` and `Hash_DRBG` mechanisms. The mechanisms themselves
+//! ([`HmacDrbg`] and [`HashDrbg`]) are deterministic and must be provided with the entropy input
+//! by the caller. [`Drbg`] wraps a mechanism together with an [`EntropySource`] (by default
+//! [`RdSeed`]) which supplies the entropy input and the nonce at instantiation, as well as fresh
+//! entropy whenever the mechanism is reseeded.
+//!
+//! ```no_run
+//! # fn main() -> Result<(), rdrand::ErrorCode> {
+//! let mut drbg = rdrand::drbg::HmacDrbgSha256::new(b"personalization string")?;
+//! drbg.set_prediction_resistance(true);
+//! let mut key = [0u8; 32];
+//! drbg.try_fill_bytes_with(&mut key, b"additional input")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! This module is only available with the `drbg` feature enabled.
+//!
+//! [NIST SP 800-90A]: https://csrc.nist.gov/publications/detail/sp/800-90a/rev-1/final
+
+#[cfg(feature = "rand_core_06")]
+use crate::busy_loop_fail;
+use crate::fork::ForkGuard;
+use crate::{EntropySource, ErrorCode, RdSeed};
+#[cfg(feature = "rand_core_06")]
+use rand_core_06::{CryptoRng, Error, RngCore};
+use sha2::{Sha256, Sha512};
+
+mod hash;
+mod hmac;
+
+pub use self::hash::HashDrbg;
+pub use self::hmac::HmacDrbg;
+
+/// `HMAC_DRBG` with SHA-256, seeded from `E`.
+pub type HmacDrbgSha256<E = RdSeed> = Drbg<HmacDrbg<Sha256>, E>;
+/// `HMAC_DRBG` with SHA-512, seeded from `E`.
+pub type HmacDrbgSha512<E = RdSeed> = Drbg<HmacDrbg<Sha512>, E>;
+/// `Hash_DRBG` with SHA-256, seeded from `E`.
+pub type HashDrbgSha256<E = RdSeed> = Drbg<HashDrbg<Sha256>, E>;
+/// `Hash_DRBG` with SHA-512, seeded from `E`.
+pub type HashDrbgSha512<E = RdSeed> = Drbg<HashDrbg<Sha512>, E>;
+
+/// The maximum number of bytes a mechanism will produce in a single generate call.
+///
+/// This is the `max_number_of_bits_per_request` limit of 2¹⁹ bits.
+pub const MAX_BYTES_PER_REQUEST: usize = 1 << 16;
+
+/// The number of generate calls after which a mechanism must be reseeded.
+pub const RESEED_INTERVAL: u64 = 1 << 48;
+
+/// The largest security strength of any mechanism in this module, in bytes.
+const MAX_SECURITY_STRENGTH: usize = 32;
+
+/// A DRBG mechanism.
+///
+/// The functions of this trait correspond to the instantiate, reseed and generate functions of the
+/// mechanism as described in SP 800-90A. The caller is responsible for providing the entropy input
+/// of appropriate length.
+pub trait Mechanism: Sized {
+    /// The security strength of the mechanism, in bytes.
+    ///
+    /// The instantiate function expects at least this many bytes of entropy input and at least
+    /// half as many bytes of nonce. The reseed function also expects at least this many bytes of
+    /// entropy input.
+    fn security_strength() -> usize;
+
+    /// Instantiate the mechanism.
+    fn instantiate(entropy_input: &[u8], nonce: &[u8], personalization_string: &[u8]) -> Self;
+
+    /// Reseed the mechanism.
+    fn reseed(&mut self, entropy_input: &[u8], additional_input: &[u8]);
+
+    /// Whether the mechanism has exhausted its reseed interval and must be reseeded before it can
+    /// generate more output.
+    fn reseed_required(&self) -> bool;
+
+    /// Fill `dest` with pseudo-random bytes.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `dest` is longer than [`MAX_BYTES_PER_REQUEST`] or if the
+    /// mechanism must be reseeded first.
+    fn generate(&mut self, dest: &mut [u8], additional_input: &[u8]);
+}
+
+/// A DRBG mechanism `M` that obtains its entropy input from `E`.
+///
+/// The mechanism is reseeded automatically once its reseed interval runs out. With prediction
+/// resistance enabled, the mechanism is additionally reseeded with fresh entropy input on every
+/// request.
+pub struct Drbg<M, E = RdSeed> {
+    mechanism: M,
+    source: E,
+    prediction_resistance: bool,
+    fork: ForkGuard,
+}
+
+impl<M: Mechanism> Drbg<M> {
+    /// Instantiate the generator using [`RdSeed`] as the source of entropy input.
+    ///
+    /// An error is returned if the `rdseed` instruction is not supported or if it fails to produce
+    /// the entropy input.
+    pub fn new(personalization_string: &[u8]) -> Result<Self, ErrorCode> {
+        Self::with_source(RdSeed::new()?, personalization_string)
+    }
+}
+
+impl<M: Mechanism, E: EntropySource> Drbg<M, E> {
+    /// Instantiate the generator using `source` as the source of entropy input.
+    ///
+    /// Prediction resistance is disabled initially.
+    pub fn with_source(mut source: E, personalization_string: &[u8]) -> Result<Self, ErrorCode> {
+        let strength = M::security_strength();
+        let mut entropy_input = [0; MAX_SECURITY_STRENGTH];
+        let mut nonce = [0; MAX_SECURITY_STRENGTH / 2];
+        source.try_fill_bytes(&mut entropy_input[..strength])?;
+        source.try_fill_bytes(&mut nonce[..strength / 2])?;
+        Ok(Drbg {
+            mechanism: M::instantiate(
+                &entropy_input[..strength],
+                &nonce[..strength / 2],
+                personalization_string,
+            ),
+            source,
+            prediction_resistance: false,
+            fork: ForkGuard::new(),
+        })
+    }
+
+    /// Enable or disable prediction resistance.
+    ///
+    /// With prediction resistance enabled, every request obtains fresh entropy input from the
+    /// source, which makes the requests considerably slower.
+    pub fn set_prediction_resistance(&mut self, enabled: bool) {
+        self.prediction_resistance = enabled;
+    }
+
+    /// Whether prediction resistance is enabled.
+    pub fn prediction_resistance(&self) -> bool {
+        self.prediction_resistance
+    }
+
+    /// Reseed the generator with fresh entropy input from the source.
+    pub fn reseed(&mut self, additional_input: &[u8]) -> Result<(), ErrorCode> {
+        let strength = M::security_strength();
+        let mut entropy_input = [0; MAX_SECURITY_STRENGTH];
+        self.source.try_fill_bytes(&mut entropy_input[..strength])?;
+        self.mechanism
+            .reseed(&entropy_input[..strength], additional_input);
+        Ok(())
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// An error is returned if the generator had to be reseeded, but the source failed to produce
+    /// the entropy input.
+    pub fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        self.try_fill_bytes_with(dest, &[])
+    }
+
+    /// Fill a buffer `dest` with random data, mixing in `additional_input`.
+    ///
+    /// Requests longer than [`MAX_BYTES_PER_REQUEST`] are split into multiple requests to the
+    /// mechanism, each of which receives the same `additional_input`.
+    pub fn try_fill_bytes_with(
+        &mut self,
+        dest: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<(), ErrorCode> {
+        if self.fork.forked() {
+            self.reseed(&[])?;
+            self.fork.reset();
+        }
+        for chunk in dest.chunks_mut(MAX_BYTES_PER_REQUEST) {
+            if self.prediction_resistance || self.mechanism.reseed_required() {
+                // The additional input is consumed by the reseed function in this case.
+                self.reseed(additional_input)?;
+                self.mechanism.generate(chunk, &[]);
+            } else {
+                self.mechanism.generate(chunk, additional_input);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<M: Mechanism, E: EntropySource> EntropySource for Drbg<M, E> {
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        Drbg::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl<M: Mechanism, E: EntropySource> RngCore for Drbg<M, E> {
+    /// Generate a single random `u32` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if the generator needs to be reseeded and the source fails to
+    /// produce the entropy input.
+    fn next_u32(&mut self) -> u32 {
+        match EntropySource::try_next_u32(self) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Generate a single random `u64` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if the generator needs to be reseeded and the source fails to
+    /// produce the entropy input.
+    fn next_u64(&mut self) -> u64 {
+        match EntropySource::try_next_u64(self) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_fill_bytes` would return an error.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match Drbg::try_fill_bytes(self, dest) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        Drbg::try_fill_bytes(self, dest).map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl<M: Mechanism, E: EntropySource + CryptoRng> CryptoRng for Drbg<M, E> {}
+
+#[cfg(feature = "rand_core_09")]
+impl<M: Mechanism, E: EntropySource> rand_core_09::TryRngCore for Drbg<M, E> {
+    type Error = ErrorCode;
+
+    /// Generate a single random `u32` value.
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        EntropySource::try_next_u32(self)
+    }
+
+    /// Generate a single random `u64` value.
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        EntropySource::try_next_u64(self)
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        Drbg::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_09")]
+impl<M: Mechanism, E: EntropySource + rand_core_09::TryCryptoRng> rand_core_09::TryCryptoRng
+    for Drbg<M, E>
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HashDrbgSha256, HmacDrbgSha256, HmacDrbgSha512};
+    use crate::mock::{hex, Replay};
+
+    // HMAC_DRBG test vectors from NIST CAVS 14.3 with prediction resistance enabled. The entropy
+    // input strings are the concatenation of EntropyInput, Nonce, EntropyInputPR for the first
+    // generate call and EntropyInputPR for the second generate call.
+    const SHA256_PR: &[(&str, &str, &str, &str, &str)] = &[
+        (
+            "9969e54b4703ff31785b879a7e5c0eae0d3e309559e9fe96b0676d49d591ea4d07d20d46d064757d3023cac2376127abc60f2999100f738c10f74792676a3fc4a262d13721798046e29a295181569f54c11d4524c9071bd3096015fcf7bc24a607f22fa065c937658a2a77a8699089f4",
+            "",
+            "",
+            "",
+            "abc015856094803a938dffd20da94843870ef935b82cfec17706b8f551b8385044235dd44b599f94b39be78dd476e0cf11309c995a7334e0a78b37bc9586235086fa3b637ba91cf8fb65efa22a589c137531aa7b2d4e2607aac27292b01c698e6e01ae679eb87c01a89c7422d4372d6d754ababb4bf896fcb1cd09d692d0283f",
+        ),
+        (
+            "4294671d493dc085b5184607d7de2ff2b6aceb734a1b026f6cfee7c5a90f03dad071544e599235d5eb38b64b551d2a6edb9b4790b62336fbb9a684b82947065393eeef8f57bd2477141ad17e776dac344a9abe80f6f522f29878bedf8245b27940a76471006fb4a4110beb4decb6c341",
+            "63bc769ae1d95a98bde870e4db7776297041d37c8a5c688d4e024b78d83f4d78",
+            "28848becd3f47696f124f4b14853a456156f69be583a7d4682cff8d44b39e1d3",
+            "8bfce0b7132661c3cd78175d83926f643e36f7608eec2c5dac3ddcbacc8c2182",
+            "e580dc969194b2b18a97478aef9d1a72390aff14562747bf080d741527a6655ce7fc135325b457483a9f9c70f91165a811cf4524b50d51199a0df3bd60d12abac27d0bf6618e6b114e05420352e23f3603dfe8a225dc19b3d1fff1dc245dc6b1df24c741744bec3f9437dbbf222df84881a457a589e7815ef132f686b760f012",
+        ),
+    ];
+
+    const SHA512_PR: &[(&str, &str, &str, &str, &str)] = &[
+        (
+            "64a8afb71975256b6196f3f93038ba8b7a4d7089f7f268134cb3f5926868e4d104c60b44fbf3bc198f4bc58bf1260d123a5aaf8749136a86c4e5aba81692d587133d29d3b7a63fa6204ed84e93be6aebf50472d313ef5797d1a290a7cae086052b57e8d5a20ed22ec7702dd424d935ea",
+            "",
+            "",
+            "",
+            "4f61f6b5d46ea351dc6f8ff55bcb915d998c8e871b5e122dd95196da241c49a1170b1fc16ffa31a6dc4f0c4068ecc6e5cc0fa6966aedf72bcb19e666b191979f22580b6505c09a784e76f58d30af3abcbe840497ad88621a893ffe13af6aef0f8276f9540068943bb6bc51498a465129880df4c517f7fe70ec239c055102a78b8b0f26d36bc2634a0e61a1431850980c258326197cc80d07c3cafc49a20316a0fa2703f850b66ce274e839d6dddba4d3e744306d768b7437ec9c54ed864c7bca4ea8d0987d815e64f685e0726eb4223aa5eac1a0979fb335248ee59819c36c7c94dadf14474c7e2f10678da59f255474ea50c3ed5ccf86a399ba7f54ae96bff0",
+        ),
+        (
+            "3aca6b55561521007c9ece085e9a6635e346fa804335d6ad42ebd6814c017fa8aa7fd3c3dd5d03d9b8efc7f70574581f4cc19fae5a456f8a53a656d23a0b665d6ddf7f43020a5febbb552714e447565d637386b3ab33f78fd9751c7b7e67e1e15f6e50ddc548a1eb5813f6d0d48381bf",
+            "4bc9a485ec840d377ae4504aa1df41e444c4231687f3d7851c26c275bc687463",
+            "b39c43539fdc24343085cbb65b8d36c54732476d781104c355c391a951313a30",
+            "b6850edd4622675ef5a507eab911e249d63fcf62f330cc8a16bb2ccc5858de5d",
+            "546664042bef33064da28a5718f2c2e5f72d7725e3fbe87ad2ee90fbfe6c114ed36440fbbccf29698b4360bc4ad74650de13825838106adc53002bc389ee900691649b972f3187b84d05cecc8fd034497dd99c6c997d1914b4ef838d84abf23fae7f3ac9efdcdc04c003ac642c5126b00f9f24bf1431a4f19ef0b5f3d230aab3fdf091ba31b7ddcacdf2566f2cfab30f55b3123e733829b697b7c8b248420ab98ba6f11b017175256368e8d8361102c9e6d57386becbeabda092dd57aec65bc20ebee78eea7294571e168c454066d256b81bb8b7bb469207a18ebedbb4348fbe97a4d86d2bd095c41f6de59aa0800e131e98181886a2633cdcc550914d83b327",
+        ),
+    ];
+
+    #[test]
+    fn hmac_sha256_prediction_resistance() {
+        for &(entropy, pers, add1, add2, expected) in SHA256_PR {
+            let entropy = hex(entropy);
+            let expected = hex(expected);
+            let mut output = [0; 128];
+            let mut drbg = HmacDrbgSha256::with_source(Replay(&entropy), &hex(pers)).unwrap();
+            drbg.set_prediction_resistance(true);
+            drbg.try_fill_bytes_with(&mut output, &hex(add1)).unwrap();
+            drbg.try_fill_bytes_with(&mut output, &hex(add2)).unwrap();
+            assert_eq!(&output[..], &expected[..]);
+        }
+    }
+
+    #[test]
+    fn hmac_sha512_prediction_resistance() {
+        for &(entropy, pers, add1, add2, expected) in SHA512_PR {
+            let entropy = hex(entropy);
+            let expected = hex(expected);
+            let mut output = [0; 256];
+            let mut drbg = HmacDrbgSha512::with_source(Replay(&entropy), &hex(pers)).unwrap();
+            drbg.set_prediction_resistance(true);
+            drbg.try_fill_bytes_with(&mut output, &hex(add1)).unwrap();
+            drbg.try_fill_bytes_with(&mut output, &hex(add2)).unwrap();
+            assert_eq!(&output[..], &expected[..]);
+        }
+    }
+
+    // The NIST CAVS 14.3 Hash_DRBG SHA-256 vector with prediction resistance, personalization
+    // string and additional input (COUNT = 14), as included in the OpenSSL FIPS provider self
+    // tests. The entropy input string is laid out as for `SHA256_PR`.
+    const HASH_SHA256_PR: (&str, &str, &str, &str, &str) = (
+        "066dc8ce75b28966a685163fe2a4d427fbdb616650616ba282fc332b4e6f1220559f7c64897083ec2d7370d9f0e5071fff80b7d26a05bc8a7abe53286b0eeb733b715a205bfa4ff63703deadb6ea0ef4c73832534681ede37e03846d3c841767297d246c689241d2e775be7ec996293d",
+        "886f549aad1ac63d18cbcc6685daa2c2f79eb0894cb4aef1ac544fce57f15e11",
+        "b7215f14ac7bafd0a91772ba22f719afbd20b311636c2b1e83e4a823353fc6ea",
+        "ced31f7e0dae5bb5c043e246b29473e2fd39512ead4569eee3e3803314aba7a3",
+        "60c234cfafb468033bf195e578ce266e1465326a96a9e03f8b893670ef62754d5e80d553a1f84950208b9343079f2ef856e9c570618597b5dc82a2daeaa3fd9b2fd2a0d71bc62935ccb83da0679805a0e31efee4f0e513b08317faca935e382948d272db763e6df32510ff1b99fff8c60eb0dd292ebcbbc80a016ed3b00e4eab",
+    );
+
+    #[test]
+    fn hash_sha256_prediction_resistance() {
+        let (entropy, pers, add1, add2, expected) = HASH_SHA256_PR;
+        let entropy = hex(entropy);
+        let mut output = [0; 128];
+        let mut drbg = HashDrbgSha256::with_source(Replay(&entropy), &hex(pers)).unwrap();
+        drbg.set_prediction_resistance(true);
+        drbg.try_fill_bytes_with(&mut output, &hex(add1)).unwrap();
+        drbg.try_fill_bytes_with(&mut output, &hex(add2)).unwrap();
+        assert_eq!(&output[..], &hex(expected)[..]);
+    }
+
+    // The NIST CAVS 14.3 HMAC_DRBG SHA-256 vector without prediction resistance, but with an
+    // explicit reseed after instantiation. The same inputs are used for `Hash_DRBG`, with the
+    // answer computed by an independent implementation of SP 800-90A.
+    const SHA256_RESEED: &str = "06032cd5eed33f39265f49ecb142c511da9aff2af71203bffaf34a9ca5bd9c0d0e66f71edc43e42a45ad3c6fc6cdc4df01920a4e669ed3a85ae8a33b35a74ad7fb2a6bb4cf395ce00334a9c9a5a5d552";
+
+    #[test]
+    fn hmac_sha256_reseed() {
+        let entropy = hex(SHA256_RESEED);
+        let mut output = [0; 128];
+        let mut drbg = HmacDrbgSha256::with_source(Replay(&entropy), b"").unwrap();
+        drbg.reseed(b"").unwrap();
+        drbg.try_fill_bytes(&mut output).unwrap();
+        drbg.try_fill_bytes(&mut output).unwrap();
+        assert_eq!(&output[..], &hex("76fc79fe9b50beccc991a11b5635783a83536add03c157fb30645e611c2898bb2b1bc215000209208cd506cb28da2a51bdb03826aaf2bd2335d576d519160842e7158ad0949d1a9ec3e66ea1b1a064b005de914eac2e9d4f2d72a8616a80225422918250ff66a41bd2f864a6a38cc5b6499dc43f7f2bd09e1e0f8f5885935124")[..]);
+    }
+
+    #[test]
+    fn hash_sha256_reseed() {
+        let entropy = hex(SHA256_RESEED);
+        let mut output = [0; 128];
+        let mut drbg = HashDrbgSha256::with_source(Replay(&entropy), b"").unwrap();
+        drbg.reseed(b"").unwrap();
+        drbg.try_fill_bytes(&mut output).unwrap();
+        drbg.try_fill_bytes(&mut output).unwrap();
+        assert_eq!(&output[..], &hex("5054d0f8dc2eb1b67e6acb623d0a3747d1af4e7fe48607e90d0332e470e230aafa203e2a3735fb9c2f37bbeabf9489a5a92aec8f9016e5ed778c60bc3f626e945f2d4209131c86ca4ae2bfce5e943bbe0458cebf3426075f1c99b887a2e41cc2bff31ea248854e0ee87dbc80146bef26195e90718531c2c7609936f1fd8941e3")[..]);
+    }
+
+    #[test]
+    fn prediction_resistance_reports_source_failure() {
+        // Enough entropy input to instantiate, but not to reseed.
+        let entropy = [0; 48];
+        let mut drbg = HmacDrbgSha256::with_source(Replay(&entropy), b"").unwrap();
+        let mut output = [0; 16];
+        drbg.try_fill_bytes(&mut output).unwrap();
+        drbg.set_prediction_resistance(true);
+        assert!(drbg.try_fill_bytes(&mut output).is_err());
+    }
+
+    #[test]
+    fn rdseed_drbg_works() {
+        let _ = HashDrbgSha256::new(b"rdrand").map(|mut drbg| {
+            let mut output = [0; 128];
+            drbg.try_fill_bytes(&mut output).unwrap();
+            drbg.set_prediction_resistance(true);
+            drbg.try_fill_bytes(&mut output).unwrap();
+        });
+    }
+}
@@ -1,7 +1,6 @@
-use core::{
-    convert::TryFrom,
-    fmt::{self, Display, Formatter},
-};
+#[cfg(feature = "rand_core_06")]
+use core::convert::TryFrom;
+use core::fmt::{self, Display, Formatter};
 
 /// Errors in this library
 #[repr(u8)]
@@ -14,25 +13,25 @@ pub enum ErrorCode {
 }
 
 impl ErrorCode {
-    #[cfg(not(feature = "std"))]
+    #[cfg(all(feature = "rand_core_06", not(feature = "std")))]
     const fn as_randcore_code(self) -> core::num::NonZeroU32 {
         /// Arbitrary, off top of head bitmask for error codes that come from rdrand
-        const RDRAND_TAG: u32 = rand_core::Error::CUSTOM_START + 0x3D34_7D00;
+        const RDRAND_TAG: u32 = rand_core_06::Error::CUSTOM_START + 0x3D34_7D00;
         unsafe { core::num::NonZeroU32::new_unchecked(RDRAND_TAG + self as u32) }
     }
 }
 
-#[cfg(not(feature = "std"))]
-impl From<ErrorCode> for rand_core::Error {
-    fn from(code: ErrorCode) -> rand_core::Error {
+#[cfg(all(feature = "rand_core_06", not(feature = "std")))]
+impl From<ErrorCode> for rand_core_06::Error {
+    fn from(code: ErrorCode) -> rand_core_06::Error {
         code.as_randcore_code().into()
     }
 }
 
-#[cfg(feature = "std")]
-impl From<ErrorCode> for rand_core::Error {
-    fn from(code: ErrorCode) -> rand_core::Error {
-        rand_core::Error::new(code)
+#[cfg(all(feature = "rand_core_06", feature = "std"))]
+impl From<ErrorCode> for rand_core_06::Error {
+    fn from(code: ErrorCode) -> rand_core_06::Error {
+        rand_core_06::Error::new(code)
     }
 }
 
@@ -48,22 +47,46 @@ impl Display for ErrorCode {
     }
 }
 
+#[cfg(feature = "std")]
+impl From<ErrorCode> for std::io::Error {
+    fn from(code: ErrorCode) -> std::io::Error {
+        let kind = match code {
+            ErrorCode::UnsupportedInstruction => std::io::ErrorKind::Unsupported,
+            ErrorCode::HardwareFailure => std::io::ErrorKind::Other,
+        };
+        std::io::Error::new(kind, code)
+    }
+}
+
+#[cfg(feature = "getrandom_03")]
+impl From<ErrorCode> for getrandom_03::Error {
+    fn from(code: ErrorCode) -> getrandom_03::Error {
+        match code {
+            ErrorCode::UnsupportedInstruction => getrandom_03::Error::UNSUPPORTED,
+            ErrorCode::HardwareFailure => getrandom_03::Error::new_custom(0x7D00 + code as u16),
+        }
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
 #[derive(Copy, Clone, Debug)]
 pub struct NotAnErrorCode;
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "rand_core_06", feature = "std"))]
 impl std::error::Error for NotAnErrorCode {}
 
+#[cfg(feature = "rand_core_06")]
 impl Display for NotAnErrorCode {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         f.write_str("the error is not an rdrand error")
     }
 }
 
-impl TryFrom<&rand_core::Error> for ErrorCode {
+#[cfg(feature = "rand_core_06")]
+impl TryFrom<&rand_core_06::Error> for ErrorCode {
     type Error = NotAnErrorCode;
     #[cfg(feature = "std")]
-    fn try_from(error: &rand_core::Error) -> Result<Self, Self::Error> {
+    fn try_from(error: &rand_core_06::Error) -> Result<Self, Self::Error> {
         error
             .inner()
             .downcast_ref::<ErrorCode>()
@@ -71,7 +94,7 @@ impl TryFrom<&rand_core::Error> for ErrorCode {
             .ok_or(NotAnErrorCode)
     }
     #[cfg(not(feature = "std"))]
-    fn try_from(error: &rand_core::Error) -> Result<Self, Self::Error> {
+    fn try_from(error: &rand_core_06::Error) -> Result<Self, Self::Error> {
         let code = error.code().ok_or(NotAnErrorCode)?;
         if code == ErrorCode::UnsupportedInstruction.as_randcore_code() {
             Ok(ErrorCode::UnsupportedInstruction)
@@ -83,18 +106,21 @@ impl TryFrom<&rand_core::Error> for ErrorCode {
     }
 }
 
-impl TryFrom<rand_core::Error> for ErrorCode {
+#[cfg(feature = "rand_core_06")]
+impl TryFrom<rand_core_06::Error> for ErrorCode {
     type Error = NotAnErrorCode;
-    fn try_from(error: rand_core::Error) -> Result<Self, Self::Error> {
-        <ErrorCode as TryFrom<&rand_core::Error>>::try_from(&error)
+    fn try_from(error: rand_core_06::Error) -> Result<Self, Self::Error> {
+        <ErrorCode as TryFrom<&rand_core_06::Error>>::try_from(&error)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::ErrorCode;
+    #[cfg(feature = "rand_core_06")]
     use core::convert::TryInto;
-    use rand_core::Error;
+    #[cfg(feature = "rand_core_06")]
+    use rand_core_06::Error;
 
     #[test]
     fn error_code_send() {
@@ -128,6 +154,7 @@ mod test {
     }
 
     #[test]
+    #[cfg(feature = "rand_core_06")]
     fn conversion_roundtrip_unsupported_hardware() {
         let core_rand: Error = ErrorCode::UnsupportedInstruction.into();
         let code: ErrorCode = core_rand.try_into().expect("should convert back");
@@ -135,6 +162,7 @@ mod test {
     }
 
     #[test]
+    #[cfg(feature = "rand_core_06")]
     fn conversion_roundtrip_hardware_failure() {
         let core_rand: Error = ErrorCode::HardwareFailure.into();
         let code: ErrorCode = core_rand.try_into().expect("should convert back");
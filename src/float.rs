@@ -0,0 +1,209 @@
+//! Generation of uniformly distributed floating-point numbers.
+use crate::{EntropySource, ErrorCode};
+
+/// The method used to generate random floating-point numbers in the unit interval.
+///
+/// The standard methods generate multiples of 2<sup>-53</sup> (2<sup>-24</sup> for `f32`) from a
+/// single word, so small numbers are generated with less precision than the type provides. The
+/// full-precision methods instead return the result of rounding a uniformly distributed real number
+/// to the type, so every representable number in the interval can be generated, with probability
+/// proportional to the distance to its neighbour. They need more than one 64-bit word only with
+/// probability 2<sup>-12</sup> for `f64` and 2<sup>-41</sup> for `f32`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FloatMode {
+    /// Multiples of 2<sup>-53</sup> (2<sup>-24</sup> for `f32`) in `[0, 1)`.
+    Standard,
+    /// Multiples of 2<sup>-53</sup> (2<sup>-24</sup> for `f32`) in `(0, 1]`.
+    StandardOpenClosed,
+    /// A uniformly distributed real number in `[0, 1)` rounded down.
+    Full,
+    /// A uniformly distributed real number in `(0, 1]` rounded up.
+    FullOpenClosed,
+}
+
+impl Default for FloatMode {
+    fn default() -> Self {
+        FloatMode::Standard
+    }
+}
+
+/// Generate a full-precision floating-point number in the unit interval.
+macro_rules! full {
+    ($name:ident, $float:ty, $bits:ty, mantissa = $mant:expr, min_exp = $min_exp:expr) => {
+        fn $name<S: EntropySource>(source: &mut S, round_up: bool) -> Result<$float, ErrorCode> {
+            let x = source.try_next_u64()?;
+            let mantissa = (x & ((1 << $mant) - 1)) as $bits;
+            // The exponent is geometrically distributed: the result lies in `[2^exp, 2^(exp+1))`
+            // with probability `2^(exp+1)`, one bit being drawn for every halving.
+            let mut bits = x >> $mant;
+            let mut available = 64 - $mant;
+            let mut exp: i32 = -1;
+            let pattern = loop {
+                if bits != 0 {
+                    exp -= bits.trailing_zeros() as i32;
+                    break if exp >= $min_exp {
+                        ((exp + 1 - $min_exp) as $bits) << $mant | mantissa
+                    } else {
+                        // The real number is below the smallest normal number, so round it on
+                        // the grid of subnormal numbers.
+                        (1 << $mant | mantissa)
+                            .checked_shr(($min_exp - exp) as u32)
+                            .unwrap_or(0)
+                    };
+                }
+                exp -= available;
+                if exp < $min_exp - $mant - 1 {
+                    break 0;
+                }
+                bits = source.try_next_u64()?;
+                available = 64;
+            };
+            // The real number is (almost surely) strictly between the two neighbours, so
+            // rounding it up yields the next number.
+            Ok(<$float>::from_bits(pattern + <$bits>::from(round_up)))
+        }
+    };
+}
+
+full!(full_f64, f64, u64, mantissa = 52, min_exp = -1022);
+full!(full_f32, f32, u32, mantissa = 23, min_exp = -126);
+
+/// Generate a random `f64` in the unit interval.
+pub(crate) fn next_f64<S: EntropySource>(
+    source: &mut S,
+    mode: FloatMode,
+) -> Result<f64, ErrorCode> {
+    const SCALE: f64 = 1.0 / (1u64 << 53) as f64;
+    match mode {
+        FloatMode::Standard => Ok((source.try_next_u64()? >> 11) as f64 * SCALE),
+        FloatMode::StandardOpenClosed => Ok(((source.try_next_u64()? >> 11) + 1) as f64 * SCALE),
+        FloatMode::Full => full_f64(source, false),
+        FloatMode::FullOpenClosed => full_f64(source, true),
+    }
+}
+
+/// Generate a random `f32` in the unit interval.
+pub(crate) fn next_f32<S: EntropySource>(
+    source: &mut S,
+    mode: FloatMode,
+) -> Result<f32, ErrorCode> {
+    const SCALE: f32 = 1.0 / (1u32 << 24) as f32;
+    match mode {
+        FloatMode::Standard => Ok((source.try_next_u32()? >> 8) as f32 * SCALE),
+        FloatMode::StandardOpenClosed => Ok(((source.try_next_u32()? >> 8) + 1) as f32 * SCALE),
+        FloatMode::Full => full_f32(source, false),
+        FloatMode::FullOpenClosed => full_f32(source, true),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{next_f32, next_f64, FloatMode};
+    use crate::mock::Replay;
+
+    const MODES: [FloatMode; 4] = [
+        FloatMode::Standard,
+        FloatMode::StandardOpenClosed,
+        FloatMode::Full,
+        FloatMode::FullOpenClosed,
+    ];
+
+    fn f64_from(input: &[u8], mode: FloatMode) -> f64 {
+        let mut source = Replay(input);
+        let result = next_f64(&mut source, mode).unwrap();
+        assert!(source.0.is_empty(), "{:?} used too few words", mode);
+        result
+    }
+
+    fn f32_from(input: &[u8], mode: FloatMode) -> f32 {
+        let mut source = Replay(input);
+        let result = next_f32(&mut source, mode).unwrap();
+        assert!(source.0.is_empty(), "{:?} used too few words", mode);
+        result
+    }
+
+    #[test]
+    fn standard_bounds() {
+        let (zero, one) = ([0; 8], [0xff; 8]);
+        assert_eq!(f64_from(&zero, FloatMode::Standard), 0.0);
+        assert_eq!(
+            f64_from(&one, FloatMode::Standard),
+            1.0 - f64::EPSILON / 2.0
+        );
+        assert_eq!(
+            f64_from(&zero, FloatMode::StandardOpenClosed),
+            f64::EPSILON / 2.0
+        );
+        assert_eq!(f64_from(&one, FloatMode::StandardOpenClosed), 1.0);
+        assert_eq!(f32_from(&zero[..4], FloatMode::Standard), 0.0);
+        assert_eq!(
+            f32_from(&one[..4], FloatMode::Standard),
+            1.0 - f32::EPSILON / 2.0
+        );
+        assert_eq!(f32_from(&one[..4], FloatMode::StandardOpenClosed), 1.0);
+    }
+
+    #[test]
+    fn full_precision() {
+        let half = 1u64 << 52;
+        assert_eq!(f64_from(&half.to_le_bytes(), FloatMode::Full), 0.5);
+        let next = f64_from(&half.to_le_bytes(), FloatMode::FullOpenClosed);
+        assert_eq!(next, 0.5 + f64::EPSILON / 2.0);
+        assert_eq!(f64_from(&[0xff; 8], FloatMode::FullOpenClosed), 1.0);
+        assert_eq!(
+            f64_from(&[0xff; 8], FloatMode::Full),
+            1.0 - f64::EPSILON / 2.0
+        );
+
+        // The exponent continues into the next word.
+        let mut input = [0; 16];
+        input[8] = 0b100;
+        assert_eq!(f64_from(&input, FloatMode::Full), 2f64.powi(-15));
+
+        let half = 1u64 << 23;
+        assert_eq!(f32_from(&half.to_le_bytes(), FloatMode::Full), 0.5);
+    }
+
+    #[test]
+    fn full_precision_subnormals() {
+        // 41 + 64 zero bits and 21 more in the third word reach 2^-127, below the smallest normal
+        // `f32`.
+        let mut input = [0; 24];
+        input[18] = 0b10_0000;
+        let result = f32_from(&input, FloatMode::Full);
+        assert!(result.is_subnormal());
+        assert_eq!(result, f32::MIN_POSITIVE / 2.0);
+
+        // Numbers below the smallest subnormal round to 0 or to the smallest subnormal.
+        let input = [0; 8 * 18];
+        assert_eq!(f64_from(&input, FloatMode::Full), 0.0);
+        assert_eq!(
+            f64_from(&input, FloatMode::FullOpenClosed),
+            f64::from_bits(1)
+        );
+    }
+
+    #[test]
+    fn stays_in_range() {
+        for mode in MODES {
+            let (len, open_closed) = match mode {
+                // The standard `f32` methods only draw 32 bits.
+                FloatMode::Standard => (4, false),
+                FloatMode::StandardOpenClosed => (4, true),
+                FloatMode::Full => (8, false),
+                FloatMode::FullOpenClosed => (8, true),
+            };
+            let in_range = |x: f64| {
+                if open_closed {
+                    x > 0.0 && x <= 1.0
+                } else {
+                    (0.0..1.0).contains(&x)
+                }
+            };
+            for input in [[0x01; 8], [0x5a; 8], [0x80; 8], [0xff; 8]] {
+                assert!(in_range(f64_from(&input, mode)));
+                assert!(in_range(f32_from(&input[..len], mode).into()));
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Detection of `fork` for the generators that keep buffered output or state.
+//!
+//! After a `fork`, parent and child hold identical copies of every generator. Without detection,
+//! both processes would hand out the same buffered bytes and derive the same output from the same
+//! state. With the `std` feature enabled on Unix, a `pthread_atfork` handler counts the forks, and
+//! every stateful generator compares the count with the one it last saw before producing output.
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of forks this process and its ancestors have gone through since the handler was
+/// registered.
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(all(unix, feature = "std"))]
+fn register() {
+    use std::os::raw::c_int;
+    use std::sync::Once;
+
+    extern "C" {
+        fn pthread_atfork(
+            prepare: Option<unsafe extern "C" fn()>,
+            parent: Option<unsafe extern "C" fn()>,
+            child: Option<unsafe extern "C" fn()>,
+        ) -> c_int;
+    }
+
+    unsafe extern "C" fn child() {
+        GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    static REGISTER: Once = Once::new();
+    // SAFETY: the handler only performs an atomic operation, which is async-signal-safe. If the
+    // registration fails for lack of memory, forks go undetected just like without `std`.
+    REGISTER.call_once(|| unsafe {
+        pthread_atfork(None, None, Some(child));
+    });
+}
+
+#[cfg(not(all(unix, feature = "std")))]
+fn register() {}
+
+/// The fork generation a generator last produced output in.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ForkGuard(usize);
+
+impl ForkGuard {
+    /// Create a guard for the current generation.
+    pub(crate) fn new() -> Self {
+        register();
+        ForkGuard(GENERATION.load(Ordering::Relaxed))
+    }
+
+    /// Create a guard for state that starts out empty, where the first check may report a fork
+    /// that didn't happen.
+    #[cfg(feature = "std")]
+    pub(crate) const fn empty() -> Self {
+        ForkGuard(usize::MAX)
+    }
+
+    /// Create a guard that reports a fork, as if the process had forked since its creation.
+    #[cfg(test)]
+    pub(crate) fn stale() -> Self {
+        ForkGuard(GENERATION.load(Ordering::Relaxed).wrapping_sub(1))
+    }
+
+    /// Whether the process has forked since the guard was created or last reset.
+    pub(crate) fn forked(&self) -> bool {
+        register();
+        GENERATION.load(Ordering::Relaxed) != self.0
+    }
+
+    /// Mark the state as belonging to the current process, once buffered output has been
+    /// discarded and the state reseeded.
+    pub(crate) fn reset(&mut self) {
+        *self = ForkGuard::new();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ForkGuard;
+
+    #[test]
+    fn detects_new_generations() {
+        let guard = ForkGuard::new();
+        assert!(!guard.forked());
+        #[cfg(feature = "std")]
+        assert!(ForkGuard::empty().forked());
+        let mut guard = ForkGuard::stale();
+        assert!(guard.forked());
+        guard.reset();
+        assert!(!guard.forked());
+    }
+}
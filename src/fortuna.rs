@@ -0,0 +1,480 @@
+#[cfg(feature = "rand_core_06")]
+use crate::busy_loop_fail;
+use crate::fork::ForkGuard;
+use crate::{EntropySource, ErrorCode, RdRand, RdSeed};
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::{Aes256, Block};
+#[cfg(feature = "rand_core_06")]
+use rand_core_06::{CryptoRng, Error, RngCore};
+use sha2::{Digest, Sha256};
+use std::boxed::Box;
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+
+/// The number of entropy pools.
+const POOLS: usize = 32;
+
+/// The number of bytes that have to be added to the first pool before the generator is reseeded.
+const MIN_POOL_SIZE: usize = 64;
+
+/// The minimum time between two reseeds of the generator.
+const RESEED_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The minimum time between two polls of the registered sources once the generator is seeded.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The largest number of bytes the generator produces before replacing its key.
+const MAX_BYTES_PER_REQUEST: usize = 1 << 20;
+
+/// The largest event added to a pool as is. Longer events are hashed first.
+const MAX_EVENT_LEN: usize = 32;
+
+/// The maximum number of polls the constructor makes while trying to seed the generator.
+const MAX_INITIAL_POLLS: usize = 64;
+
+/// The identifier of an entropy source registered with a [`Fortuna`] accumulator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceId(u8);
+
+/// The AES-256 counter mode generator of Fortuna.
+#[derive(Default)]
+struct Generator {
+    key: [u8; 32],
+    /// The generator has not been seeded as long as the counter is zero.
+    counter: u128,
+}
+
+impl Generator {
+    /// Replace the key with the hash of the current key and `seed`.
+    fn reseed(&mut self, seed: &[u8]) {
+        let inner = Sha256::new()
+            .chain_update(self.key)
+            .chain_update(seed)
+            .finalize();
+        self.key.copy_from_slice(&Sha256::digest(inner));
+        self.counter = self.counter.wrapping_add(1);
+    }
+
+    fn is_seeded(&self) -> bool {
+        self.counter != 0
+    }
+
+    fn generate_blocks(&mut self, cipher: &Aes256, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(16) {
+            let mut block = Block::from(self.counter.to_le_bytes());
+            cipher.encrypt_block(&mut block);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            self.counter = self.counter.wrapping_add(1);
+        }
+    }
+
+    /// Fill `dest` with keystream and replace the key with the following two blocks.
+    fn pseudo_random_data(&mut self, dest: &mut [u8]) {
+        assert!(dest.len() <= MAX_BYTES_PER_REQUEST, "request too long");
+        assert!(self.is_seeded(), "generator not seeded");
+        let cipher = Aes256::new(&self.key.into());
+        self.generate_blocks(&cipher, dest);
+        let mut key = [0; 32];
+        self.generate_blocks(&cipher, &mut key);
+        self.key = key;
+    }
+}
+
+/// Adapts the operating system's random number generator to an [`EntropySource`].
+struct OsSource;
+
+impl EntropySource for OsSource {
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        getrandom::getrandom(dest).map_err(|_| ErrorCode::HardwareFailure)
+    }
+}
+
+struct PolledSource {
+    id: SourceId,
+    source: Box<dyn EntropySource + Send>,
+}
+
+/// A [Fortuna] random number generator accumulating entropy from several sources.
+///
+/// Hardware entropy is only one input among several: [`RdSeed`], [`RdRand`] and the operating
+/// system's random number generator are polled for 32 bytes each at most every 100 milliseconds,
+/// and arbitrary other data, such as event timings, can be added with [`add_event`]. Each source
+/// distributes its samples over 32 SHA-256 pools in turn. The AES-256 counter mode generator is
+/// reseeded once enough data has been added to the first pool, at most every 100 milliseconds,
+/// with the `i`-th pool used in every `2^i`-th reseed. An attacker who controls or observes some
+/// of the sources thus cannot prevent the generator from eventually recovering from a compromise.
+///
+/// Sources that fail to produce a sample are skipped.
+///
+/// This generator is only available with the `fortuna` feature enabled.
+///
+/// [Fortuna]: https://www.schneier.com/academic/fortuna/
+/// [`add_event`]: Fortuna::add_event
+pub struct Fortuna {
+    generator: Generator,
+    pools: [Sha256; POOLS],
+    /// The number of bytes added to the first pool since it was last used.
+    pool0_len: usize,
+    reseed_count: u64,
+    last_reseed: Option<Instant>,
+    last_poll: Option<Instant>,
+    polled: Vec<PolledSource>,
+    /// The pool the next event of each source goes to, indexed by the source identifier.
+    next_pool: Vec<usize>,
+    fork: ForkGuard,
+}
+
+impl Fortuna {
+    /// Create a new generator with `RdSeed`, `RdRand` and the operating system's random number
+    /// generator registered as sources and seed it.
+    ///
+    /// The instructions not supported by the CPU are not registered. An error is returned if the
+    /// sources fail to produce enough data to seed the generator.
+    pub fn new() -> Result<Self, ErrorCode> {
+        let mut fortuna = Self::unseeded();
+        if let Ok(rdseed) = RdSeed::new() {
+            fortuna.add_source(rdseed);
+        }
+        if let Ok(rdrand) = RdRand::new() {
+            fortuna.add_source(rdrand);
+        }
+        fortuna.add_source(OsSource);
+        for _ in 0..MAX_INITIAL_POLLS {
+            fortuna.poll();
+            if fortuna.reseed_due() {
+                fortuna.reseed();
+                return Ok(fortuna);
+            }
+        }
+        Err(ErrorCode::HardwareFailure)
+    }
+
+    /// Create a new generator without any sources.
+    ///
+    /// The generator fails to produce output until sources are registered and enough data has
+    /// been gathered from them to seed it.
+    pub fn unseeded() -> Self {
+        Fortuna {
+            generator: Generator::default(),
+            pools: Default::default(),
+            pool0_len: 0,
+            reseed_count: 0,
+            last_reseed: None,
+            last_poll: None,
+            polled: Vec::new(),
+            next_pool: Vec::new(),
+            fork: ForkGuard::new(),
+        }
+    }
+
+    /// Register a source the generator polls for entropy.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if 256 sources are already registered.
+    pub fn add_source<S: EntropySource + Send + 'static>(&mut self, source: S) -> SourceId {
+        let id = self.add_event_source();
+        self.polled.push(PolledSource {
+            id,
+            source: Box::new(source),
+        });
+        id
+    }
+
+    /// Register a source of events added with [`add_event`](Fortuna::add_event).
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if 256 sources are already registered.
+    pub fn add_event_source(&mut self) -> SourceId {
+        assert!(self.next_pool.len() <= 0xFF, "too many sources");
+        let id = SourceId(self.next_pool.len() as u8);
+        self.next_pool.push(0);
+        id
+    }
+
+    /// Add an event from `source` to the pools.
+    ///
+    /// Events longer than 32 bytes are hashed first and empty events are ignored.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if `source` was not registered with this generator.
+    pub fn add_event(&mut self, source: SourceId, event: &[u8]) {
+        let digest;
+        let event = if event.len() > MAX_EVENT_LEN {
+            digest = Sha256::digest(event);
+            &digest[..]
+        } else {
+            event
+        };
+        if event.is_empty() {
+            return;
+        }
+        let next_pool = &mut self.next_pool[usize::from(source.0)];
+        let pool = *next_pool;
+        *next_pool = (pool + 1) % POOLS;
+        self.pools[pool].update([source.0, event.len() as u8]);
+        self.pools[pool].update(event);
+        if pool == 0 {
+            self.pool0_len += 2 + event.len();
+        }
+    }
+
+    /// Whether the generator has been seeded.
+    pub fn is_seeded(&self) -> bool {
+        self.generator.is_seeded()
+    }
+
+    fn poll(&mut self) {
+        let mut sample = [0; MAX_EVENT_LEN];
+        for i in 0..self.polled.len() {
+            if self.polled[i].source.try_fill_bytes(&mut sample).is_ok() {
+                let id = self.polled[i].id;
+                self.add_event(id, &sample);
+            }
+        }
+        self.last_poll = Some(Instant::now());
+    }
+
+    fn poll_due(&self) -> bool {
+        !self.is_seeded() || elapsed(self.last_poll, POLL_INTERVAL)
+    }
+
+    fn reseed_due(&self) -> bool {
+        self.pool0_len >= MIN_POOL_SIZE && elapsed(self.last_reseed, RESEED_INTERVAL)
+    }
+
+    fn reseed(&mut self) {
+        self.reseed_count += 1;
+        let mut seed = [0; 32 * POOLS];
+        let mut len = 0;
+        for (i, pool) in self.pools.iter_mut().enumerate() {
+            if !uses_pool(self.reseed_count, i) {
+                break;
+            }
+            let digest = Sha256::digest(core::mem::take(pool).finalize());
+            seed[len..len + 32].copy_from_slice(&digest);
+            len += 32;
+        }
+        self.generator.reseed(&seed[..len]);
+        self.pool0_len = 0;
+        self.last_reseed = Some(Instant::now());
+    }
+
+    /// Reseed the generator directly from the polled sources, so that the generators of parent and
+    /// child processes diverge immediately after a fork.
+    ///
+    /// An error is returned if none of the sources produces a sample.
+    fn reseed_after_fork(&mut self) -> Result<(), ErrorCode> {
+        let mut seed = Sha256::new();
+        let mut sampled = false;
+        let mut sample = [0; MAX_EVENT_LEN];
+        for polled in &mut self.polled {
+            if polled.source.try_fill_bytes(&mut sample).is_ok() {
+                seed.update(sample);
+                sampled = true;
+            }
+        }
+        if !sampled {
+            return Err(ErrorCode::HardwareFailure);
+        }
+        self.generator.reseed(&seed.finalize());
+        Ok(())
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// An error is returned if the generator has not been seeded yet, or if the process has forked
+    /// and none of the sources produces a sample to reseed the generator with.
+    pub fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        if self.poll_due() {
+            self.poll();
+        }
+        if self.reseed_due() {
+            self.reseed();
+        }
+        if !self.is_seeded() {
+            return Err(ErrorCode::HardwareFailure);
+        }
+        if self.fork.forked() {
+            self.reseed_after_fork()?;
+            self.fork.reset();
+        }
+        for chunk in dest.chunks_mut(MAX_BYTES_PER_REQUEST) {
+            self.generator.pseudo_random_data(chunk);
+        }
+        Ok(())
+    }
+}
+
+/// Whether `interval` has passed since `since`, or `since` is `None`.
+fn elapsed(since: Option<Instant>, interval: Duration) -> bool {
+    match since {
+        Some(t) => t.elapsed() >= interval,
+        None => true,
+    }
+}
+
+/// Whether the `reseed_count`-th reseed uses the `pool`-th pool.
+fn uses_pool(reseed_count: u64, pool: usize) -> bool {
+    reseed_count & ((1 << pool) - 1) == 0
+}
+
+impl EntropySource for Fortuna {
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        Fortuna::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl RngCore for Fortuna {
+    /// Generate a single random `u32` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if the generator has not been seeded yet.
+    fn next_u32(&mut self) -> u32 {
+        match EntropySource::try_next_u32(self) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Generate a single random `u64` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if the generator has not been seeded yet.
+    fn next_u64(&mut self) -> u64 {
+        match EntropySource::try_next_u64(self) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_fill_bytes` would return an error.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match Fortuna::try_fill_bytes(self, dest) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        Fortuna::try_fill_bytes(self, dest).map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl CryptoRng for Fortuna {}
+
+#[cfg(feature = "rand_core_09")]
+impl rand_core_09::TryRngCore for Fortuna {
+    type Error = ErrorCode;
+
+    /// Generate a single random `u32` value.
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        EntropySource::try_next_u32(self)
+    }
+
+    /// Generate a single random `u64` value.
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        EntropySource::try_next_u64(self)
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        Fortuna::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_09")]
+impl rand_core_09::TryCryptoRng for Fortuna {}
+
+#[cfg(test)]
+mod test {
+    use super::{uses_pool, Fortuna, Generator, MIN_POOL_SIZE, POOLS};
+    use crate::mock::{hex, Replay};
+
+    #[test]
+    fn generator_known_answer() {
+        // Computed with an independent implementation on top of the AES-256 of OpenSSL.
+        let mut generator = Generator::default();
+        generator.reseed(b"rdrand");
+        let mut output = [0; 40];
+        generator.pseudo_random_data(&mut output);
+        assert_eq!(
+            &output[..],
+            &hex(
+                "90cb9f39131cbb6b68789222e9ed9c314aef9b8f0cdade2be5b425db2eb0e6b8886100dedb1f3e71"
+            )[..]
+        );
+        let mut output = [0; 16];
+        generator.pseudo_random_data(&mut output);
+        assert_eq!(&output[..], &hex("23382ca4947512e9c28cb24bc6dcb855")[..]);
+    }
+
+    #[test]
+    fn pool_schedule() {
+        let used = |r| (0..POOLS).filter(|&i| uses_pool(r, i)).count();
+        assert_eq!(used(1), 1);
+        assert_eq!(used(2), 2);
+        assert_eq!(used(3), 1);
+        assert_eq!(used(12), 3);
+        assert_eq!(used(1 << 31), 32);
+        assert_eq!(used(1 << 40), 32);
+    }
+
+    #[test]
+    fn seeds_from_events() {
+        let mut fortuna = Fortuna::unseeded();
+        let id = fortuna.add_event_source();
+        assert!(fortuna.try_fill_bytes(&mut [0; 8]).is_err());
+        // Every 32nd event of a source goes to the first pool.
+        for i in 0..POOLS {
+            fortuna.add_event(id, &[i as u8; 32]);
+        }
+        assert!(fortuna.pool0_len < MIN_POOL_SIZE);
+        assert!(fortuna.try_fill_bytes(&mut [0; 8]).is_err());
+        for i in 0..POOLS {
+            fortuna.add_event(id, &[i as u8; 100]);
+        }
+        assert!(fortuna.pool0_len >= MIN_POOL_SIZE);
+        fortuna.try_fill_bytes(&mut [0; 8]).unwrap();
+        assert!(fortuna.is_seeded());
+        assert_eq!(fortuna.reseed_count, 1);
+        // Reseeds are rate limited.
+        for _ in 0..POOLS * 2 {
+            fortuna.add_event(id, &[0xFF; 32]);
+        }
+        fortuna.try_fill_bytes(&mut [0; 8]).unwrap();
+        assert_eq!(fortuna.reseed_count, 1);
+    }
+
+    #[test]
+    fn skips_failing_sources() {
+        let mut fortuna = Fortuna::unseeded();
+        fortuna.add_source(Replay(&[]));
+        assert!(fortuna.try_fill_bytes(&mut [0; 8]).is_err());
+        fortuna.add_source(Replay(&[1; 32]));
+        fortuna.add_source(Replay(&[2; 32]));
+        fortuna.try_fill_bytes(&mut [0; 8]).unwrap();
+        assert_eq!(fortuna.reseed_count, 1);
+    }
+
+    #[test]
+    fn fortuna_works() {
+        let mut fortuna = Fortuna::new().unwrap();
+        let mut buffer = [0; 1000];
+        fortuna.try_fill_bytes(&mut buffer).unwrap();
+        let id = fortuna.add_event_source();
+        fortuna.add_event(id, &[1, 2, 3]);
+        fortuna.try_fill_bytes(&mut buffer).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+//! Support code for [`register_getrandom_backend!`](crate::register_getrandom_backend).
+use crate::{EntropySource, ErrorCode};
+
+pub use getrandom_03::Error;
+
+/// Register a generator from this crate as the custom backend of `getrandom` 0.3.
+///
+/// Every crate that obtains its randomness through `getrandom` (such as `rand`, `uuid` or `ring`)
+/// then transparently uses the hardware generator instead of the operating system, which is useful
+/// in sandboxes that filter the `getrandom` system call.
+///
+/// The macro takes an expression evaluating to `Result<S, ErrorCode>` for some
+/// [`EntropySource`](crate::EntropySource) `S`, which is evaluated for every request. It defaults
+/// to a copy of [`RdRand::shared`](crate::RdRand::shared), so support for the instruction is
+/// detected on the first request only. If the CPU does not support the instruction, requests
+/// fail with `getrandom::Error::UNSUPPORTED`. Hardware failures are reported as
+/// `getrandom::Error::new_custom(0x7D01)`.
+///
+/// The macro must be invoked exactly once, in the root crate of the program (such as in
+/// `main.rs`), and `getrandom` must be configured to use the custom backend by passing
+/// `--cfg getrandom_backend="custom"` to rustc, for example through the `RUSTFLAGS` environment
+/// variable. `getrandom` 0.2 does not allow overriding its backend on supported targets, so only
+/// version 0.3 is supported.
+///
+/// This macro is only available with the `getrandom_03` feature enabled.
+///
+/// # Examples
+///
+/// Use `rdseed` instead of `rdrand`:
+///
+/// ```no_run
+/// rdrand::register_getrandom_backend!(rdrand::RdSeed::new());
+/// ```
+#[macro_export]
+macro_rules! register_getrandom_backend {
+    () => {
+        $crate::register_getrandom_backend!($crate::RdRand::shared().map(|&rng| rng));
+    };
+    ($source:expr) => {
+        #[no_mangle]
+        unsafe extern "Rust" fn __getrandom_v03_custom(
+            dest: *mut u8,
+            len: usize,
+        ) -> ::core::result::Result<(), $crate::getrandom_backend::Error> {
+            $crate::getrandom_backend::fill($source, dest, len)
+        }
+    };
+}
+
+/// Fill `len` bytes at `dest` with data from `source`.
+///
+/// # Safety
+///
+/// `dest` must be valid for writes of `len` bytes. The bytes may be uninitialized, as `getrandom`
+/// passes them; they are zeroed before a `&mut [u8]` is created for `source` to fill.
+pub unsafe fn fill<S: EntropySource>(
+    source: Result<S, ErrorCode>,
+    dest: *mut u8,
+    len: usize,
+) -> Result<(), Error> {
+    core::ptr::write_bytes(dest, 0, len);
+    let dest = core::slice::from_raw_parts_mut(dest, len);
+    source
+        .and_then(|mut source| source.try_fill_bytes(dest))
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fill, Error};
+    use crate::mock::Replay;
+    use crate::ErrorCode;
+
+    crate::register_getrandom_backend!();
+
+    #[test]
+    fn fill_maps_errors() {
+        let mut dest = [0; 4];
+        let source = Ok(Replay(&[1, 2, 3, 4]));
+        unsafe { fill(source, dest.as_mut_ptr(), dest.len()) }.unwrap();
+        assert_eq!(dest, [1, 2, 3, 4]);
+
+        let source = Ok(Replay(&[1, 2, 3]));
+        let error = unsafe { fill(source, dest.as_mut_ptr(), dest.len()) }.unwrap_err();
+        assert_eq!(error, Error::new_custom(0x7D01));
+
+        let source = Err::<Replay, _>(ErrorCode::UnsupportedInstruction);
+        let error = unsafe { fill(source, dest.as_mut_ptr(), dest.len()) }.unwrap_err();
+        assert_eq!(error, Error::UNSUPPORTED);
+    }
+
+    #[test]
+    fn registered_backend_works() {
+        let mut dest = [0; 17];
+        let result = unsafe { __getrandom_v03_custom(dest.as_mut_ptr(), dest.len()) };
+        if crate::RdRand::new().is_ok() {
+            result.unwrap();
+        } else {
+            assert_eq!(result, Err(Error::UNSUPPORTED));
+        }
+    }
+}
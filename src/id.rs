@@ -0,0 +1,95 @@
+//! Generation of universally unique identifiers as specified by RFC 9562.
+use crate::{EntropySource, ErrorCode};
+
+/// Set the version and variant fields of `bytes`.
+fn set_version(mut bytes: [u8; 16], version: u8) -> [u8; 16] {
+    bytes[6] = bytes[6] & 0x0f | version << 4;
+    bytes[8] = bytes[8] & 0x3f | 0x80;
+    bytes
+}
+
+/// Generate a random (version 4) UUID from `source` as specified by RFC 9562.
+///
+/// The 122 random bits of the UUID are drawn with two calls to `try_next_u64`. The bytes are in the
+/// order defined by the RFC and can be converted with `Uuid::from_bytes`.
+pub fn uuid_v4<S: EntropySource>(source: &mut S) -> Result<[u8; 16], ErrorCode> {
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&source.try_next_u64()?.to_le_bytes());
+    bytes[8..].copy_from_slice(&source.try_next_u64()?.to_le_bytes());
+    Ok(set_version(bytes, 4))
+}
+
+/// Generate a time-ordered (version 7) UUID from `source` as specified by RFC 9562.
+///
+/// `unix_ts_ms` is the number of milliseconds since the Unix epoch, of which the lower 48 bits are
+/// used. The 74 random bits of the UUID are drawn with one call to `try_next_u16` and one call to
+/// `try_next_u64`. The bytes are in the order defined by the RFC and can be converted with
+/// `Uuid::from_bytes`.
+pub fn uuid_v7<S: EntropySource>(source: &mut S, unix_ts_ms: u64) -> Result<[u8; 16], ErrorCode> {
+    let mut bytes = [0; 16];
+    bytes[..6].copy_from_slice(&unix_ts_ms.to_be_bytes()[2..]);
+    bytes[6..8].copy_from_slice(&source.try_next_u16()?.to_le_bytes());
+    bytes[8..].copy_from_slice(&source.try_next_u64()?.to_le_bytes());
+    Ok(set_version(bytes, 7))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{uuid_v4, uuid_v7};
+    use crate::mock::{hex, Replay};
+    use crate::{RdRand, RdSeed};
+
+    #[test]
+    fn sets_version_and_variant() {
+        let ones = [0xff; 16];
+        let mut source = Replay(&ones);
+        let expected = hex("ffffffffffff4fffbfffffffffffffff");
+        assert_eq!(&uuid_v4(&mut source).unwrap()[..], &expected[..]);
+        assert!(source.0.is_empty());
+
+        let input = hex("0102030405060708090a0b0c0d0e0f10");
+        let expected = hex("0102030405064708890a0b0c0d0e0f10");
+        assert_eq!(&uuid_v4(&mut Replay(&input)).unwrap()[..], &expected[..]);
+    }
+
+    #[test]
+    fn v7_layout() {
+        let input = hex("0c4cc0318c4dc0c0c7b2");
+        let mut source = Replay(&input);
+        // Bits of the timestamp above the lower 48 are ignored.
+        let uuid = uuid_v7(&mut source, 0xffff_0184_71a4_2b17).unwrap();
+        let expected = hex("018471a42b177c4c80318c4dc0c0c7b2");
+        assert_eq!(&uuid[..], &expected[..]);
+        assert!(source.0.is_empty());
+    }
+
+    #[test]
+    fn uuids_work() {
+        let _ = RdRand::new().map(|r| {
+            let uuid = r.try_uuid_v4().unwrap();
+            assert_eq!(uuid[6] >> 4, 4);
+            let uuid = r.try_uuid_v7(u64::MAX).unwrap();
+            assert_eq!(&uuid[..6], &[0xff; 6]);
+            assert_eq!(uuid[6] >> 4, 7);
+        });
+    }
+
+    #[test]
+    fn rdseed_uuids_work() {
+        let _ = RdSeed::new().map(|r| {
+            assert_eq!(r.try_uuid_v4().unwrap()[6] >> 4, 4);
+            assert_eq!(r.try_uuid_v7(0).unwrap()[6] >> 4, 7);
+        });
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn converts_to_uuid() {
+        let _ = RdRand::new().map(|r| {
+            assert_eq!(r.try_new_uuid_v4().unwrap().get_version_num(), 4);
+            let uuid = r.try_new_uuid_v7(1_700_000_000_000).unwrap();
+            assert_eq!(uuid.get_version_num(), 7);
+            assert_eq!(uuid.get_variant(), uuid::Variant::RFC4122);
+        });
+    }
+}
@@ -19,6 +19,16 @@
 //! Unless you know what you are doing, use the random number generators provided by the `rand`
 //! crate (such as `OsRng`) instead.
 //!
+//! [`global`] (or [`RdRand::shared`]) returns a handle to a generator shared by the whole program,
+//! detecting support for the instruction only once. The free functions [`fill`] and [`u64`](fn@u64) draw
+//! from it directly. With the `std` feature enabled, `thread_rdrand` returns a handle that serves
+//! many small draws faster from a buffer per thread.
+//!
+//! The generators and adapters that buffer output or keep state detect when the process has
+//! forked: the child discards the buffered output and reseeds the state from the hardware before
+//! producing any output, so that parent and child never hand out the same bytes. Detection
+//! requires the `std` feature and a Unix target.
+//!
 //! Here are a measurements for select processor architectures. Check [Agner’s instruction tables]
 //! for up-to-date listings.
 //!
@@ -59,13 +69,119 @@
 //! </table>
 //!
 //! [Agner’s instruction tables]: http://agner.org/optimize/
+//!
+//! The generators implement the traits of several major versions of `rand_core` at the same time,
+//! each enabled by a feature:
+//!
+//! * `rand_core_06` (enabled by default): `RngCore` and `CryptoRng` from `rand_core` 0.6. The
+//!   methods of `RngCore` panic where the inherent `try_*` methods would return an error.
+//! * `rand_core_09`: `TryRngCore` and `TryCryptoRng` from `rand_core` 0.9, with [`ErrorCode`] as
+//!   the error type. Use `TryRngCore::unwrap_err` where an infallible `RngCore` is needed, and
+//!   `SeedableRng::try_from_rng` to seed other generators from [`RdSeed`].
+//!
+//! With the `uuid` feature enabled, `RdRand` and `RdSeed` can generate `uuid::Uuid`s directly.
+//!
+//! With the `zeroize` feature enabled, [`SecretBytes`] and `SecretVec` implement `Zeroize` and
+//! `ZeroizeOnDrop`.
+//!
+//! With the `locked` feature enabled on Linux, `LockedSecret` keeps secret random bytes in
+//! memory that is locked into RAM and excluded from core dumps.
+//!
+//! The library requires rustc 1.60 with the `std` and `rand_core_06` features disabled. The
+//! current releases of some dependencies require a newer compiler whenever the feature that pulls
+//! them in is enabled:
+//!
+//! * `std` together with `rand_core_06`, both enabled by default, and `fortuna`: rustc 1.65 on
+//!   Unix, for `libc`.
+//! * `chacha`: rustc 1.61, for `ppv-lite86`.
+//! * `getrandom_03`: rustc 1.65 on Unix, for `libc`, and 1.63 elsewhere.
+//! * `locked`: rustc 1.65, for `libc`.
+//! * `rand_core_09`: rustc 1.63.
+//! * `tokio`: rustc 1.71.
+//! * `uuid`: rustc 1.89.
+//! * `zeroize`: rustc 1.85.
+//!
+//! Older releases of these dependencies that support rustc 1.60 can be selected with
+//! `cargo update --precise`.
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "tokio")]
+mod async_read;
+mod bits;
+#[cfg(any(feature = "seed-from-rdrand", feature = "conditioned"))]
+mod cbc_mac;
+#[cfg(feature = "chacha")]
+mod chacha;
 pub mod changelog;
+#[cfg(feature = "conditioned")]
+mod conditioned;
+#[cfg(feature = "drbg")]
+pub mod drbg;
 mod errors;
+mod float;
+mod fork;
+#[cfg(feature = "fortuna")]
+mod fortuna;
+#[cfg(feature = "getrandom_03")]
+#[doc(hidden)]
+pub mod getrandom_backend;
+mod id;
+#[cfg(all(feature = "locked", target_os = "linux"))]
+mod locked;
+#[cfg(test)]
+mod mock;
+mod range;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "rand_core_06")]
+mod reseeding;
+mod secret;
+#[cfg(feature = "seed-from-rdrand")]
+mod seed;
+mod seq;
+mod shared;
+#[cfg(feature = "futures")]
+mod stream;
+#[cfg(feature = "std")]
+mod thread;
+#[cfg(feature = "std")]
+mod token;
 
+#[cfg(feature = "tokio")]
+pub use async_read::AsyncRdReader;
+pub use bits::BitSource;
+#[cfg(feature = "chacha")]
+pub use chacha::ChaChaRdRng;
+#[cfg(feature = "conditioned")]
+pub use conditioned::{Conditioned, Conditioner};
 pub use errors::ErrorCode;
-use rand_core::{CryptoRng, Error, RngCore};
+pub use float::FloatMode;
+#[cfg(feature = "fortuna")]
+pub use fortuna::{Fortuna, SourceId};
+pub use id::{uuid_v4, uuid_v7};
+#[cfg(all(feature = "locked", target_os = "linux"))]
+pub use locked::LockedSecret;
+#[cfg(feature = "std")]
+pub use reader::RdReader;
+#[cfg(feature = "rand_core_06")]
+pub use reseeding::Reseeding;
+pub use secret::SecretBytes;
+#[cfg(feature = "std")]
+pub use secret::SecretVec;
+#[cfg(feature = "seed-from-rdrand")]
+pub use seed::SeedFromRdRand;
+pub use shared::{fill, global, u64};
+#[cfg(feature = "futures")]
+pub use stream::RdStream;
+#[cfg(feature = "std")]
+pub use thread::{thread_rdrand, ThreadRdRand};
+#[cfg(feature = "std")]
+pub use token::{Alphabet, TokenGenerator};
+
+use core::mem::MaybeUninit;
+use core::ops::RangeBounds;
+#[cfg(feature = "rand_core_06")]
+use rand_core_06::{CryptoRng, Error, RngCore, SeedableRng};
 
 #[cold]
 #[inline(never)]
@@ -95,12 +211,90 @@ pub struct RdRand(());
 /// This instruction is only supported by recent architectures such as Intel Broadwell and AMD Zen.
 ///
 /// This generator is not intended for general random number generation purposes and should be used
-/// to seed other generators implementing [rand_core::SeedableRng].
+/// to seed other generators implementing `SeedableRng`.
 #[derive(Clone, Copy)]
 pub struct RdSeed(());
 
+#[cfg(feature = "rand_core_06")]
 impl CryptoRng for RdRand {}
+#[cfg(feature = "rand_core_06")]
 impl CryptoRng for RdSeed {}
+#[cfg(feature = "rand_core_09")]
+impl rand_core_09::TryCryptoRng for RdRand {}
+#[cfg(feature = "rand_core_09")]
+impl rand_core_09::TryCryptoRng for RdSeed {}
+
+/// A fallible source of random bits.
+///
+/// This trait is implemented by the generators in this crate and is used to parametrize the
+/// constructions built on top of them (such as the generators in the `drbg` module) over the
+/// source of their entropy input.
+///
+/// The methods are equivalent to the inherent methods of the same name on [`RdRand`] and
+/// [`RdSeed`].
+pub trait EntropySource {
+    /// Generate a single random `u16` value.
+    ///
+    /// The default implementation converts two bytes from `try_fill_bytes` in native byte order.
+    fn try_next_u16(&mut self) -> Result<u16, ErrorCode> {
+        let mut bytes = [0; 2];
+        self.try_fill_bytes(&mut bytes)?;
+        Ok(u16::from_ne_bytes(bytes))
+    }
+    /// Generate a single random `u32` value.
+    ///
+    /// The default implementation converts four bytes from `try_fill_bytes` in native byte order.
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        let mut bytes = [0; 4];
+        self.try_fill_bytes(&mut bytes)?;
+        Ok(u32::from_ne_bytes(bytes))
+    }
+    /// Generate a single random `u64` value.
+    ///
+    /// The default implementation converts eight bytes from `try_fill_bytes` in native byte
+    /// order.
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        let mut bytes = [0; 8];
+        self.try_fill_bytes(&mut bytes)?;
+        Ok(u64::from_ne_bytes(bytes))
+    }
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode>;
+
+    /// Fill a prefix of the buffer `dest` with random data without waiting for the source to
+    /// recover from a temporary exhaustion.
+    ///
+    /// Returns the length of the prefix, which is shorter than `dest` if the source ran out of
+    /// entropy. [`RdRand`] and [`RdSeed`] execute the instruction once per word instead of
+    /// retrying. The default implementation calls `try_fill_bytes` and thus fills `dest`
+    /// entirely or fails.
+    fn try_fill_bytes_nonblocking(&mut self, dest: &mut [u8]) -> Result<usize, ErrorCode> {
+        self.try_fill_bytes(dest).map(|()| dest.len())
+    }
+}
+
+impl<S: EntropySource + ?Sized> EntropySource for &mut S {
+    #[inline(always)]
+    fn try_next_u16(&mut self) -> Result<u16, ErrorCode> {
+        (**self).try_next_u16()
+    }
+    #[inline(always)]
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        (**self).try_next_u32()
+    }
+    #[inline(always)]
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        (**self).try_next_u64()
+    }
+    #[inline(always)]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        (**self).try_fill_bytes(dest)
+    }
+    #[inline(always)]
+    fn try_fill_bytes_nonblocking(&mut self, dest: &mut [u8]) -> Result<usize, ErrorCode> {
+        (**self).try_fill_bytes_nonblocking(dest)
+    }
+}
 
 mod arch {
     #[cfg(target_arch = "x86")]
@@ -159,6 +353,7 @@ macro_rules! loop_rand {
 }
 
 #[inline(always)]
+#[allow(unused_unsafe)] // `__cpuid` is safe to call in newer versions of Rust.
 fn authentic_amd() -> bool {
     let cpuid0 = unsafe { arch::__cpuid(0) };
     matches!(
@@ -179,6 +374,7 @@ fn has_rdrand(cpuid1: &arch::CpuidResult) -> bool {
 }
 
 #[inline(always)]
+#[allow(unused_unsafe)]
 fn has_rdseed() -> bool {
     const FLAG: u32 = 1 << 18;
     unsafe { arch::__cpuid(7).ebx & FLAG == FLAG }
@@ -196,8 +392,14 @@ fn has_rdseed() -> bool {
 /// specified, in order to prevent users from shooting themselves in their feet.
 const FIRST_GOOD_AMD_FAMILY: u32 = 0x17;
 
+/// The number of consecutive polls without progress after which the asynchronous adapters report a
+/// hardware failure.
+#[cfg(any(feature = "tokio", feature = "futures"))]
+const MAX_EMPTY_POLLS: u32 = 128;
+
 macro_rules! is_available {
     ("rdrand") => {{
+        #[allow(unused_unsafe)]
         if authentic_amd() {
             let cpuid1 = unsafe { arch::__cpuid(1) };
             has_rdrand(&cpuid1) && amd_family(&cpuid1) >= FIRST_GOOD_AMD_FAMILY
@@ -206,6 +408,7 @@ macro_rules! is_available {
         }
     }};
     ("rdseed") => {{
+        #[allow(unused_unsafe)]
         if authentic_amd() {
             amd_family(&unsafe { arch::__cpuid(1) }) >= FIRST_GOOD_AMD_FAMILY && has_rdseed()
         } else {
@@ -310,6 +513,63 @@ macro_rules! impl_rand {
                 unsafe { imp() }
             }
 
+            /// Generate a single random `u128` value.
+            ///
+            /// The value is made of two 64-bit values generated as described for
+            /// `try_next_u64`, the first of which forms the low half.
+            ///
+            /// In case `Err` is returned, the caller should assume that a non-recoverable failure
+            /// has occured and use another random number genrator instead.
+            #[inline(always)]
+            pub fn try_next_u128(&self) -> Result<u128, ErrorCode> {
+                #[target_feature(enable = $feat)]
+                unsafe fn imp() -> Result<u128, ErrorCode> {
+                    let low = loop_rand!($feat, u64, $step64)?;
+                    let high = loop_rand!($feat, u64, $step64)?;
+                    Ok(u128::from(high) << 64 | u128::from(low))
+                }
+                unsafe { imp() }
+            }
+
+            /// Generate an array of `N` random bytes.
+            ///
+            /// The widest variant of the instruction is executed once per whole word of the array
+            /// and the remaining bytes are produced by a single execution of the narrowest variant
+            /// that covers them. At most three bytes of output are discarded, when a tail of five
+            /// bytes is cut from a `u64`.
+            ///
+            /// In case `Err` is returned, the caller should assume that a non-recoverable failure
+            /// has occured and use another random number genrator instead.
+            #[inline(always)]
+            pub fn try_next_array<const N: usize>(&self) -> Result<[u8; N], ErrorCode> {
+                #[target_feature(enable = $feat)]
+                unsafe fn imp<const N: usize>() -> Result<[u8; N], ErrorCode> {
+                    const WORD: usize = ::core::mem::size_of::<$maxty>();
+                    let mut array = [0; N];
+                    let mut chunks = array.chunks_exact_mut(WORD);
+                    for chunk in &mut chunks {
+                        chunk.copy_from_slice(&loop_rand!($feat, $maxty, $maxstep)?.to_ne_bytes());
+                    }
+                    let tail = chunks.into_remainder();
+                    let len = tail.len();
+                    if len > 4 {
+                        tail.copy_from_slice(
+                            &loop_rand!($feat, u64, $step64)?.to_ne_bytes()[..len],
+                        );
+                    } else if len > 2 {
+                        tail.copy_from_slice(
+                            &loop_rand!($feat, u32, $step32)?.to_ne_bytes()[..len],
+                        );
+                    } else if len > 0 {
+                        tail.copy_from_slice(
+                            &loop_rand!($feat, u16, $step16)?.to_ne_bytes()[..len],
+                        );
+                    }
+                    Ok(array)
+                }
+                unsafe { imp() }
+            }
+
             /// Fill a buffer `dest` with random data.
             ///
             /// This method will use the most appropriate variant of the instruction available on
@@ -327,13 +587,29 @@ macro_rules! impl_rand {
             /// failure has occured and use another random number genrator instead.
             #[inline(always)]
             pub fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+                // SAFETY: `try_fill_uninit` only ever writes initialized bytes to `dest`.
+                let dest = unsafe { &mut *(dest as *mut [u8] as *mut [MaybeUninit<u8>]) };
+                self.try_fill_uninit(dest).map(|_| ())
+            }
+
+            /// Fill a possibly uninitialized buffer `dest` with random data.
+            ///
+            /// This method behaves like `try_fill_bytes`, but does not require `dest` to be
+            /// initialized first. On success it returns `dest` as an initialized slice. If an
+            /// error is returned, the contents of `dest` are unspecified and may remain partially
+            /// uninitialized.
+            #[inline(always)]
+            pub fn try_fill_uninit<'a>(
+                &mut self,
+                dest: &'a mut [MaybeUninit<u8>],
+            ) -> Result<&'a mut [u8], ErrorCode> {
                 #[target_feature(enable = $feat)]
-                unsafe fn imp(dest: &mut [u8]) -> Result<(), ErrorCode> {
+                unsafe fn imp(dest: &mut [MaybeUninit<u8>]) -> Result<(), ErrorCode> {
                     fn slow_fill_bytes<'a>(
-                        mut left: &'a mut [u8],
-                        mut right: &'a mut [u8],
+                        mut left: &'a mut [MaybeUninit<u8>],
+                        mut right: &'a mut [MaybeUninit<u8>],
                     ) -> Result<(), ErrorCode> {
-                        let mut word;
+                        let mut word = crate::secret::Wiped([0; ::core::mem::size_of::<$maxty>()]);
                         let mut buffer: &[u8] = &[];
                         loop {
                             if left.is_empty() {
@@ -343,25 +619,27 @@ macro_rules! impl_rand {
                                 ::core::mem::swap(&mut left, &mut right);
                             }
                             if buffer.is_empty() {
-                                word =
+                                word.0 =
                                     unsafe { loop_rand!($feat, $maxty, $maxstep) }?.to_ne_bytes();
-                                buffer = &word[..];
+                                buffer = &word.0[..];
                             }
                             let len = left.len().min(buffer.len());
                             let (copy_src, leftover) = buffer.split_at(len);
                             let (copy_dest, dest_leftover) = { left }.split_at_mut(len);
                             buffer = leftover;
                             left = dest_leftover;
-                            copy_dest.copy_from_slice(copy_src);
+                            for (dest, src) in copy_dest.iter_mut().zip(copy_src) {
+                                *dest = MaybeUninit::new(*src);
+                            }
                         }
                         Ok(())
                     }
 
                     let destlen = dest.len();
                     if destlen > ::core::mem::size_of::<$maxty>() {
-                        let (left, mid, right) = dest.align_to_mut();
+                        let (left, mid, right) = dest.align_to_mut::<MaybeUninit<$maxty>>();
                         for el in mid {
-                            *el = loop_rand!($feat, $maxty, $maxstep)?;
+                            *el = MaybeUninit::new(loop_rand!($feat, $maxty, $maxstep)?);
                         }
 
                         slow_fill_bytes(left, right)
@@ -369,10 +647,403 @@ macro_rules! impl_rand {
                         slow_fill_bytes(dest, &mut [])
                     }
                 }
+                unsafe { imp(dest)? };
+                // SAFETY: `imp` has initialized every byte of `dest` if it succeeded.
+                Ok(unsafe { &mut *(dest as *mut [MaybeUninit<u8>] as *mut [u8]) })
+            }
+
+            /// Generate a uniformly distributed random `u32` value in `range`.
+            ///
+            /// The value is sampled without bias with Lemire's nearly divisionless method, using
+            /// the 16-bit variant of the instruction when the range has at most 65536 values.
+            ///
+            /// The instruction is retried as described for `try_next_u32`. If an error is
+            /// returned, the caller should assume that an non-recoverable hardware failure has
+            /// occured and use another random number genrator instead.
+            ///
+            /// # Panics
+            ///
+            /// This method panics if `range` is empty.
+            #[inline]
+            pub fn try_gen_range_u32<R: RangeBounds<u32>>(
+                &mut self,
+                range: R,
+            ) -> Result<u32, ErrorCode> {
+                let (low, high) = crate::range::inclusive_bounds(&range, u32::MAX.into());
+                crate::range::gen_inclusive(self, low, high).map(|x| x as u32)
+            }
+
+            /// Generate a uniformly distributed random `u64` value in `range`.
+            ///
+            /// The value is sampled without bias with Lemire's nearly divisionless method, using
+            /// the narrowest variant of the instruction that covers the range.
+            ///
+            /// The instruction is retried as described for `try_next_u64`. If an error is
+            /// returned, the caller should assume that an non-recoverable hardware failure has
+            /// occured and use another random number genrator instead.
+            ///
+            /// # Panics
+            ///
+            /// This method panics if `range` is empty.
+            #[inline]
+            pub fn try_gen_range_u64<R: RangeBounds<u64>>(
+                &mut self,
+                range: R,
+            ) -> Result<u64, ErrorCode> {
+                let (low, high) = crate::range::inclusive_bounds(&range, u64::MAX);
+                crate::range::gen_inclusive(self, low, high)
+            }
+
+            /// Shuffle `slice` in place.
+            ///
+            /// Every permutation is equally likely: the Fisher-Yates algorithm draws every swap
+            /// directly from this generator with the unbiased sampling of `try_gen_range_u64`.
+            ///
+            /// If an error is returned, `slice` is left partially shuffled and the caller should
+            /// assume that an non-recoverable hardware failure has occured.
+            #[inline]
+            pub fn try_shuffle<T>(&mut self, slice: &mut [T]) -> Result<(), ErrorCode> {
+                crate::seq::shuffle(self, slice)
+            }
+
+            /// Choose a uniformly distributed element of `slice`.
+            ///
+            /// `None` is returned if `slice` is empty.
+            ///
+            /// If an error is returned, the caller should assume that an non-recoverable hardware
+            /// failure has occured and use another random number genrator instead.
+            #[inline]
+            pub fn try_choose<'a, T>(
+                &mut self,
+                slice: &'a [T],
+            ) -> Result<Option<&'a T>, ErrorCode> {
+                crate::seq::choose(self, slice)
+            }
+
+            /// Choose `k` distinct indices in `0..n`.
+            ///
+            /// Every sequence of distinct indices is equally likely, so the indices are also
+            /// returned in a uniformly random order.
+            ///
+            /// If an error is returned, the caller should assume that an non-recoverable hardware
+            /// failure has occured and use another random number genrator instead.
+            ///
+            /// This method is only available with the `std` feature enabled.
+            ///
+            /// # Panics
+            ///
+            /// This method panics if `k` is greater than `n`.
+            #[cfg(feature = "std")]
+            #[inline]
+            pub fn try_sample_indices(
+                &mut self,
+                n: usize,
+                k: usize,
+            ) -> Result<std::vec::Vec<usize>, ErrorCode> {
+                crate::seq::sample_indices(self, n, k)
+            }
+
+            /// Generate a uniformly distributed random `f64` value in the unit interval.
+            ///
+            /// See [`FloatMode`] for the available methods and the intervals they cover.
+            ///
+            /// If an error is returned, the caller should assume that an non-recoverable hardware
+            /// failure has occured and use another random number genrator instead.
+            #[inline]
+            pub fn try_next_f64(&mut self, mode: FloatMode) -> Result<f64, ErrorCode> {
+                crate::float::next_f64(self, mode)
+            }
+
+            /// Generate a uniformly distributed random `f32` value in the unit interval.
+            ///
+            /// See [`FloatMode`] for the available methods and the intervals they cover.
+            ///
+            /// If an error is returned, the caller should assume that an non-recoverable hardware
+            /// failure has occured and use another random number genrator instead.
+            #[inline]
+            pub fn try_next_f32(&mut self, mode: FloatMode) -> Result<f32, ErrorCode> {
+                crate::float::next_f32(self, mode)
+            }
+
+            /// Generate a random (version 4) UUID as specified by RFC 9562.
+            ///
+            /// The 122 random bits of the UUID are drawn with exactly two executions of the 64-bit
+            /// variant of the instruction (four executions of the 32-bit variant on 32-bit
+            /// targets). The bytes are in the order defined by the RFC and can be converted with
+            /// `Uuid::from_bytes`. Other generators can use [`uuid_v4`].
+            ///
+            /// If an error is returned, the caller should assume that an non-recoverable hardware
+            /// failure has occured and use another random number genrator instead.
+            #[inline]
+            pub fn try_uuid_v4(&self) -> Result<[u8; 16], ErrorCode> {
+                crate::id::uuid_v4(&mut { *self })
+            }
+
+            /// Generate a time-ordered (version 7) UUID as specified by RFC 9562.
+            ///
+            /// `unix_ts_ms` is the number of milliseconds since the Unix epoch, of which the lower
+            /// 48 bits are used. The 74 random bits of the UUID are drawn with one execution of the
+            /// 16-bit variant and one execution of the 64-bit variant of the instruction. The bytes
+            /// are in the order defined by the RFC and can be converted with `Uuid::from_bytes`.
+            /// Other generators can use [`uuid_v7`].
+            ///
+            /// If an error is returned, the caller should assume that an non-recoverable hardware
+            /// failure has occured and use another random number genrator instead.
+            #[inline]
+            pub fn try_uuid_v7(&self, unix_ts_ms: u64) -> Result<[u8; 16], ErrorCode> {
+                crate::id::uuid_v7(&mut { *self }, unix_ts_ms)
+            }
+
+            /// Generate a random (version 4) `uuid::Uuid`.
+            ///
+            /// See `try_uuid_v4` for details.
+            ///
+            /// This method is only available with the `uuid` feature enabled.
+            #[cfg(feature = "uuid")]
+            #[inline]
+            pub fn try_new_uuid_v4(&self) -> Result<uuid::Uuid, ErrorCode> {
+                self.try_uuid_v4().map(uuid::Uuid::from_bytes)
+            }
+
+            /// Generate a time-ordered (version 7) `uuid::Uuid`.
+            ///
+            /// See `try_uuid_v7` for details.
+            ///
+            /// This method is only available with the `uuid` feature enabled.
+            #[cfg(feature = "uuid")]
+            #[inline]
+            pub fn try_new_uuid_v7(&self, unix_ts_ms: u64) -> Result<uuid::Uuid, ErrorCode> {
+                self.try_uuid_v7(unix_ts_ms).map(uuid::Uuid::from_bytes)
+            }
+
+            /// Fill several buffers `bufs` with random data.
+            ///
+            /// The bytes of a word left over at the end of one buffer are used at the start of
+            /// the next one, so filling buffers with a total length of `len` bytes executes the
+            /// instruction `ceil(len / size_of::<usize>())` times regardless of how the bytes are
+            /// split between buffers.
+            ///
+            /// The instruction is retried as described for `try_fill_bytes`. If an error is
+            /// returned, the caller should assume that an non-recoverable hardware failure has
+            /// occured and use another random number genrator instead.
+            ///
+            /// This method is only available with the `std` feature enabled.
+            #[cfg(feature = "std")]
+            #[inline(always)]
+            pub fn try_fill_bytes_vectored(
+                &mut self,
+                bufs: &mut [std::io::IoSliceMut<'_>],
+            ) -> Result<(), ErrorCode> {
+                #[target_feature(enable = $feat)]
+                unsafe fn imp(bufs: &mut [std::io::IoSliceMut<'_>]) -> Result<(), ErrorCode> {
+                    let mut word = crate::secret::Wiped([0; ::core::mem::size_of::<$maxty>()]);
+                    // The bytes `word[used..]` have not been handed out yet.
+                    let mut used = word.0.len();
+                    for buf in bufs {
+                        let len = buf.len().min(word.0.len() - used);
+                        let (head, dest) = buf.split_at_mut(len);
+                        head.copy_from_slice(&word.0[used..used + len]);
+                        used += len;
+                        let mut chunks = dest.chunks_exact_mut(word.0.len());
+                        for chunk in &mut chunks {
+                            chunk.copy_from_slice(
+                                &loop_rand!($feat, $maxty, $maxstep)?.to_ne_bytes(),
+                            );
+                        }
+                        let tail = chunks.into_remainder();
+                        if !tail.is_empty() {
+                            word.0 = loop_rand!($feat, $maxty, $maxstep)?.to_ne_bytes();
+                            tail.copy_from_slice(&word.0[..tail.len()]);
+                            used = tail.len();
+                        }
+                    }
+                    Ok(())
+                }
+                unsafe { imp(bufs) }
+            }
+
+            /// Fill a buffer `dest` with random `u16` values.
+            ///
+            /// Unlike `try_fill_bytes`, this method executes the 16-bit variant of the
+            /// instruction once per element, so no output is discarded.
+            ///
+            /// The instruction is retried as described for `try_next_u16`. If an error is
+            /// returned, the caller should assume that an non-recoverable hardware failure has
+            /// occured and use another random number genrator instead.
+            #[inline(always)]
+            pub fn try_fill_u16(&mut self, dest: &mut [u16]) -> Result<(), ErrorCode> {
+                #[target_feature(enable = $feat)]
+                unsafe fn imp(dest: &mut [u16]) -> Result<(), ErrorCode> {
+                    for el in dest {
+                        *el = loop_rand!($feat, u16, $step16)?;
+                    }
+                    Ok(())
+                }
                 unsafe { imp(dest) }
             }
+
+            /// Fill a buffer `dest` with random `u32` values.
+            ///
+            /// Unlike `try_fill_bytes`, this method executes the 32-bit variant of the
+            /// instruction once per element, so no output is discarded.
+            ///
+            /// The instruction is retried as described for `try_next_u32`. If an error is
+            /// returned, the caller should assume that an non-recoverable hardware failure has
+            /// occured and use another random number genrator instead.
+            #[inline(always)]
+            pub fn try_fill_u32(&mut self, dest: &mut [u32]) -> Result<(), ErrorCode> {
+                #[target_feature(enable = $feat)]
+                unsafe fn imp(dest: &mut [u32]) -> Result<(), ErrorCode> {
+                    for el in dest {
+                        *el = loop_rand!($feat, u32, $step32)?;
+                    }
+                    Ok(())
+                }
+                unsafe { imp(dest) }
+            }
+
+            /// Fill a buffer `dest` with random `u64` values.
+            ///
+            /// Unlike `try_fill_bytes`, this method executes the 64-bit variant of the
+            /// instruction once per element, so no output is discarded. On 32-bit targets it is
+            /// emulated with two executions of the 32-bit variant.
+            ///
+            /// The instruction is retried as described for `try_next_u64`. If an error is
+            /// returned, the caller should assume that an non-recoverable hardware failure has
+            /// occured and use another random number genrator instead.
+            #[inline(always)]
+            pub fn try_fill_u64(&mut self, dest: &mut [u64]) -> Result<(), ErrorCode> {
+                #[target_feature(enable = $feat)]
+                unsafe fn imp(dest: &mut [u64]) -> Result<(), ErrorCode> {
+                    for el in dest {
+                        *el = loop_rand!($feat, u64, $step64)?;
+                    }
+                    Ok(())
+                }
+                unsafe { imp(dest) }
+            }
+
+            /// Fill a buffer `dest` with random `u128` values.
+            ///
+            /// Every element is made of two 64-bit values generated as described for
+            /// `try_fill_u64`, the first of which forms the low half.
+            ///
+            /// If an error is returned, the caller should assume that an non-recoverable hardware
+            /// failure has occured and use another random number genrator instead.
+            #[inline(always)]
+            pub fn try_fill_u128(&mut self, dest: &mut [u128]) -> Result<(), ErrorCode> {
+                #[target_feature(enable = $feat)]
+                unsafe fn imp(dest: &mut [u128]) -> Result<(), ErrorCode> {
+                    for el in dest {
+                        let low = loop_rand!($feat, u64, $step64)?;
+                        let high = loop_rand!($feat, u64, $step64)?;
+                        *el = u128::from(high) << 64 | u128::from(low);
+                    }
+                    Ok(())
+                }
+                unsafe { imp(dest) }
+            }
+
+            /// Fill a buffer `dest` with random `u16` values.
+            ///
+            /// # Panic
+            ///
+            /// This method will panic any time `try_fill_u16` would return an error.
+            #[inline(always)]
+            pub fn fill_u16(&mut self, dest: &mut [u16]) {
+                if let Err(c) = self.try_fill_u16(dest) {
+                    busy_loop_fail(c)
+                }
+            }
+
+            /// Fill a buffer `dest` with random `u32` values.
+            ///
+            /// # Panic
+            ///
+            /// This method will panic any time `try_fill_u32` would return an error.
+            #[inline(always)]
+            pub fn fill_u32(&mut self, dest: &mut [u32]) {
+                if let Err(c) = self.try_fill_u32(dest) {
+                    busy_loop_fail(c)
+                }
+            }
+
+            /// Fill a buffer `dest` with random `u64` values.
+            ///
+            /// # Panic
+            ///
+            /// This method will panic any time `try_fill_u64` would return an error.
+            #[inline(always)]
+            pub fn fill_u64(&mut self, dest: &mut [u64]) {
+                if let Err(c) = self.try_fill_u64(dest) {
+                    busy_loop_fail(c)
+                }
+            }
+
+            /// Fill a buffer `dest` with random `u128` values.
+            ///
+            /// # Panic
+            ///
+            /// This method will panic any time `try_fill_u128` would return an error.
+            #[inline(always)]
+            pub fn fill_u128(&mut self, dest: &mut [u128]) {
+                if let Err(c) = self.try_fill_u128(dest) {
+                    busy_loop_fail(c)
+                }
+            }
+
+            /// Fill a buffer `dest` of any plain-old-data type with random data.
+            ///
+            /// The buffer is filled as if by `try_fill_bytes`, so every bit pattern must be a
+            /// valid value of `T`, as guaranteed by `bytemuck::Pod`.
+            ///
+            /// This method is only available with the `bytemuck` feature enabled.
+            #[cfg(feature = "bytemuck")]
+            #[inline(always)]
+            pub fn try_fill<T: bytemuck::Pod>(&mut self, dest: &mut [T]) -> Result<(), ErrorCode> {
+                self.try_fill_bytes(bytemuck::cast_slice_mut(dest))
+            }
+        }
+
+        impl EntropySource for $gen {
+            #[inline(always)]
+            fn try_next_u16(&mut self) -> Result<u16, ErrorCode> {
+                $gen::try_next_u16(self)
+            }
+            #[inline(always)]
+            fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+                $gen::try_next_u32(self)
+            }
+            #[inline(always)]
+            fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+                $gen::try_next_u64(self)
+            }
+            #[inline(always)]
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+                $gen::try_fill_bytes(self, dest)
+            }
+            #[inline(always)]
+            fn try_fill_bytes_nonblocking(&mut self, dest: &mut [u8]) -> Result<usize, ErrorCode> {
+                #[target_feature(enable = $feat)]
+                unsafe fn imp(dest: &mut [u8]) -> usize {
+                    let mut filled = 0;
+                    let mut bytes = crate::secret::Wiped([0; ::core::mem::size_of::<$maxty>()]);
+                    for chunk in dest.chunks_mut(bytes.0.len()) {
+                        let mut word: $maxty = 0;
+                        if $maxstep(&mut word) == 0 {
+                            break;
+                        }
+                        bytes.0 = word.to_ne_bytes();
+                        chunk.copy_from_slice(&bytes.0[..chunk.len()]);
+                        filled += chunk.len();
+                    }
+                    filled
+                }
+                Ok(unsafe { imp(dest) })
+            }
         }
 
+        #[cfg(feature = "rand_core_06")]
         impl RngCore for $gen {
             /// Generate a single random `u32` value.
             ///
@@ -454,6 +1125,35 @@ macro_rules! impl_rand {
                 self.try_fill_bytes(dest).map_err(Into::into)
             }
         }
+
+        #[cfg(feature = "rand_core_09")]
+        impl rand_core_09::TryRngCore for $gen {
+            type Error = ErrorCode;
+
+            /// Generate a single random `u32` value.
+            ///
+            /// See the inherent method of the same name for a more extensive documentation.
+            #[inline(always)]
+            fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+                $gen::try_next_u32(self)
+            }
+
+            /// Generate a single random `u64` value.
+            ///
+            /// See the inherent method of the same name for a more extensive documentation.
+            #[inline(always)]
+            fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+                $gen::try_next_u64(self)
+            }
+
+            /// Fill a buffer `dest` with random data.
+            ///
+            /// See the inherent method of the same name for a more extensive documentation.
+            #[inline(always)]
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+                $gen::try_fill_bytes(self, dest)
+            }
+        }
     };
 }
 
@@ -498,10 +1198,166 @@ impl_rand!(
     maxty = u32
 );
 
+#[cfg(feature = "rand_core_06")]
+impl RdSeed {
+    /// Create a new instance of the generator `R` seeded by this generator.
+    ///
+    /// If an error is returned, the caller should assume that an non-recoverable hardware failure
+    /// has occured and use another random number genrator instead.
+    ///
+    /// This method is only available with the `rand_core_06` feature enabled.
+    pub fn seed<R: SeedableRng>(&mut self) -> Result<R, ErrorCode> {
+        let mut seed = R::Seed::default();
+        self.try_fill_bytes(seed.as_mut())?;
+        Ok(R::from_seed(seed))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::{RdRand, RdSeed};
-    use rand_core::RngCore;
+
+    #[test]
+    fn fill_uninit_fills_all_bytes() {
+        use core::mem::MaybeUninit;
+        let _ = RdRand::new().map(|mut r| {
+            let mut buffer = [MaybeUninit::<u8>::uninit(); 64];
+            for &(start, end) in &[(0, 64), (5, 63), (1, 7), (3, 3)] {
+                let filled = r.try_fill_uninit(&mut buffer[start..end]).unwrap();
+                assert_eq!(filled.len(), end - start);
+            }
+        });
+    }
+
+    #[test]
+    fn next_array_works() {
+        fn check<const N: usize>(r: &RdRand) {
+            let mut acc = [0; N];
+            for _ in 0..64 {
+                for (a, b) in acc.iter_mut().zip(&r.try_next_array::<N>().unwrap()) {
+                    *a |= b;
+                }
+            }
+            assert!(acc.iter().all(|&x| x != 0), "{:?}", acc);
+        }
+        let _ = RdRand::new().map(|r| {
+            check::<0>(&r);
+            check::<1>(&r);
+            check::<3>(&r);
+            check::<5>(&r);
+            check::<6>(&r);
+            check::<7>(&r);
+            check::<16>(&r);
+            check::<21>(&r);
+            check::<63>(&r);
+            let x = r.try_next_u128().unwrap();
+            assert!(x >> 64 != 0 && x as u64 != 0);
+        });
+        let _ = RdSeed::new().map(|r| {
+            r.try_next_array::<32>().unwrap();
+            r.try_next_u128().unwrap();
+        });
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn fill_vectored_fills_all_bytes() {
+        use std::io::IoSliceMut;
+        let _ = RdRand::new().map(|mut r| {
+            let mut acc = [[0u8; 17]; 4];
+            for _ in 0..64 {
+                let mut bufs = [[0; 17]; 4];
+                let [a, b, c, d] = &mut bufs;
+                r.try_fill_bytes_vectored(&mut [
+                    IoSliceMut::new(&mut a[..3]),
+                    IoSliceMut::new(&mut []),
+                    IoSliceMut::new(&mut c[..9]),
+                    IoSliceMut::new(&mut d[..17]),
+                    IoSliceMut::new(&mut b[..5]),
+                ])
+                .unwrap();
+                for (acc, buf) in acc.iter_mut().zip(&bufs) {
+                    for (x, y) in acc.iter_mut().zip(buf) {
+                        *x |= y;
+                    }
+                }
+            }
+            for (acc, &len) in acc.iter().zip(&[3, 5, 9, 17]) {
+                assert!(acc[..len].iter().all(|&x| x != 0), "{:?}", acc);
+                assert!(acc[len..].iter().all(|&x| x == 0), "{:?}", acc);
+            }
+        });
+    }
+
+    #[test]
+    fn gen_range_works() {
+        let _ = RdRand::new().map(|mut r| {
+            for _ in 0..100 {
+                assert!((10..20).contains(&r.try_gen_range_u32(10..20).unwrap()));
+                assert!(r.try_gen_range_u32(..=3).unwrap() <= 3);
+                assert!(r.try_gen_range_u64(1 << 40..).unwrap() >= 1 << 40);
+            }
+        });
+        let _ = RdSeed::new().map(|mut r| r.try_gen_range_u64(0..1_000_000_007).unwrap());
+    }
+
+    #[test]
+    fn floats_work() {
+        use crate::FloatMode;
+        let _ = RdRand::new().map(|mut r| {
+            let x = r.try_next_f64(FloatMode::Full).unwrap();
+            assert!((0.0..1.0).contains(&x));
+            let x = r.try_next_f32(FloatMode::StandardOpenClosed).unwrap();
+            assert!(x > 0.0 && x <= 1.0);
+        });
+    }
+
+    #[test]
+    fn sequences_work() {
+        let _ = RdRand::new().map(|mut r| {
+            let mut items = [1, 2, 3, 4, 5];
+            r.try_shuffle(&mut items).unwrap();
+            items.sort_unstable();
+            assert_eq!(items, [1, 2, 3, 4, 5]);
+            assert!(items.contains(r.try_choose(&items).unwrap().unwrap()));
+            #[cfg(feature = "std")]
+            assert_eq!(r.try_sample_indices(100, 10).unwrap().len(), 10);
+        });
+    }
+
+    #[test]
+    fn fill_words_fills_all_elements() {
+        let _ = RdRand::new().map(|mut r| {
+            r.fill_u16(&mut [0; 33]);
+            r.try_fill_u32(&mut [0; 33]).unwrap();
+            let mut dest64 = [0; 33];
+            r.fill_u64(&mut dest64);
+            assert!(dest64.iter().all(|&x| x != 0));
+            let mut dest128 = [0; 33];
+            r.try_fill_u128(&mut dest128).unwrap();
+            assert!(dest128.iter().all(|&x| x >> 64 != 0 && x as u64 != 0));
+        });
+        let _ = RdSeed::new().map(|mut r| {
+            r.try_fill_u64(&mut [0; 4]).unwrap();
+            r.fill_u128(&mut [0; 4]);
+        });
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn fill_pod_works() {
+        let _ = RdRand::new().map(|mut r| {
+            let mut dest = [[0u32; 3]; 7];
+            r.try_fill(&mut dest).unwrap();
+            assert!(dest.iter().flatten().any(|&x| x != 0));
+        });
+    }
+}
+
+#[cfg(all(test, feature = "rand_core_06"))]
+mod test_rand_core_06 {
+    use super::{RdRand, RdSeed};
+    use rand_core_06::RngCore;
 
     #[test]
     fn rdrand_works() {
@@ -527,10 +1383,10 @@ mod test {
                 (0, 63), // left is empty, right is non-empty.
                 (5, 63), // left and right both are non-empty.
                 (5, 61), // left and right both are non-empty.
-                (0, 8),   // 1 word-worth of data, aligned.
-                (1, 9),   // 1 word-worth of data, misaligned.
-                (0, 7),   // less than 1 word of data.
-                (1, 7),   // less than 1 word of data.
+                (0, 8),  // 1 word-worth of data, aligned.
+                (1, 9),  // 1 word-worth of data, misaligned.
+                (0, 7),  // less than 1 word of data.
+                (1, 7),  // less than 1 word of data.
             ];
             'outer: for &(start, end) in &test_cases {
                 test_buffer = [0; 64];
@@ -538,9 +1394,9 @@ mod test {
                     fill_buffer.0 = [0; 64];
                     r.fill_bytes(&mut fill_buffer.0[start..end]);
                     for (b, p) in test_buffer.iter_mut().zip(fill_buffer.0.iter()) {
-                        *b = *b | *p;
+                        *b |= *p;
                     }
-                    if (&test_buffer[start..end]).iter().all(|x| *x != 0) {
+                    if test_buffer[start..end].iter().all(|x| *x != 0) {
                         assert!(
                             test_buffer[..start].iter().all(|x| *x == 0),
                             "all other values must be 0"
@@ -565,3 +1421,24 @@ mod test {
         });
     }
 }
+
+#[cfg(all(test, feature = "rand_core_09"))]
+mod test_rand_core_09 {
+    use super::{RdRand, RdSeed};
+    use rand_core_09::{RngCore, TryCryptoRng, TryRngCore};
+
+    #[test]
+    fn rand_core_09_works() {
+        fn assert_crypto<R: TryCryptoRng<Error = crate::ErrorCode>>(_: &R) {}
+        let _ = RdRand::new().map(|r| {
+            assert_crypto(&r);
+            let mut r = r.unwrap_err();
+            r.next_u32();
+            r.fill_bytes(&mut [0; 17]);
+        });
+        let _ = RdSeed::new().map(|mut r| {
+            assert_crypto(&r);
+            TryRngCore::try_next_u64(&mut r).unwrap();
+        });
+    }
+}
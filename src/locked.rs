@@ -0,0 +1,239 @@
+//! Secret random bytes in memory that is never swapped out or written to core dumps.
+use crate::secret::{constant_time_eq, wipe};
+use crate::{EntropySource, RdSeed};
+use core::fmt;
+use std::io;
+
+/// Turn the result of a libc call into an `io::Result`.
+fn check(result: std::os::raw::c_int) -> io::Result<()> {
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Secret random bytes in locked memory, such as a long-lived master key.
+///
+/// The bytes live in pages of their own that are mapped with `mmap`, locked into RAM with `mlock`
+/// so that they are never swapped out, and excluded from core dumps with
+/// `madvise(MADV_DONTDUMP)`. The pages are surrounded by inaccessible guard pages, and the bytes
+/// are placed at the end of the last page, so that overruns in either direction fault instead of
+/// reading or overwriting other memory.
+///
+/// The locked pages are filled by [`RdSeed`] (or another [`EntropySource`]) in place, so the bytes
+/// never pass through an unlocked buffer. They are wiped before the pages are unlocked and
+/// unmapped when the value is dropped. Like [`SecretBytes`](crate::SecretBytes), the `Debug`
+/// implementation does not reveal the contents and comparisons take constant time.
+///
+/// Locking memory is subject to the `RLIMIT_MEMLOCK` resource limit, which is often only a few
+/// hundred kilobytes for unprivileged processes. Every value occupies at least one locked page.
+///
+/// This type is only available on Linux with the `locked` feature enabled.
+pub struct LockedSecret {
+    /// The start of the mapping, including the guard pages.
+    map: *mut u8,
+    map_len: usize,
+    page: usize,
+    /// The number of secret bytes at the end of the last data page.
+    len: usize,
+}
+
+// SAFETY: `LockedSecret` owns its mapping exclusively and only hands out references tied to its
+// own lifetime.
+unsafe impl Send for LockedSecret {}
+unsafe impl Sync for LockedSecret {}
+
+impl LockedSecret {
+    /// Generate `len` secret bytes in locked memory with [`RdSeed`].
+    ///
+    /// An error is returned if the `rdseed` instruction is not supported or fails, or if the
+    /// memory cannot be mapped or locked.
+    pub fn new(len: usize) -> io::Result<Self> {
+        Self::from_source(&mut RdSeed::new()?, len)
+    }
+
+    /// Generate `len` secret bytes in locked memory with `source`.
+    ///
+    /// An error is returned if `source` fails, or if the memory cannot be mapped or locked.
+    pub fn from_source<S: EntropySource>(source: &mut S, len: usize) -> io::Result<Self> {
+        let secret = Self::allocate(len)?;
+        // SAFETY: the secret bytes are mapped readable and writable and owned by `secret`.
+        let dest = unsafe { core::slice::from_raw_parts_mut(secret.data(), len) };
+        source.try_fill_bytes(dest)?;
+        Ok(secret)
+    }
+
+    /// Map, protect and lock memory for `len` zero bytes.
+    fn allocate(len: usize) -> io::Result<Self> {
+        // SAFETY: `sysconf` has no preconditions.
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let data_len = len
+            .checked_add(page - 1)
+            .map(|len| (len / page).max(1) * page)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "secret too large"))?;
+        let map_len = data_len + 2 * page;
+        // SAFETY: mapping new anonymous memory does not affect any existing memory.
+        let map = unsafe {
+            libc::mmap(
+                core::ptr::null_mut(),
+                map_len,
+                libc::PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if map == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // From here on, `drop` unmaps the memory if any of the following steps fails. The length
+        // stays 0 until the data pages are accessible, so that nothing is wiped before.
+        let mut secret = LockedSecret {
+            map: map as *mut u8,
+            map_len,
+            page,
+            len: 0,
+        };
+        let (pages, data_len) = secret.data_pages();
+        // SAFETY: the data pages are part of the mapping owned by `secret`.
+        unsafe {
+            check(libc::mprotect(
+                pages,
+                data_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+            ))?;
+            check(libc::mlock(pages, data_len))?;
+            check(libc::madvise(pages, data_len, libc::MADV_DONTDUMP))?;
+        }
+        secret.len = len;
+        Ok(secret)
+    }
+
+    /// The pages between the guard pages and their total length.
+    fn data_pages(&self) -> (*mut libc::c_void, usize) {
+        // SAFETY: the first guard page is part of the mapping.
+        let pages = unsafe { self.map.add(self.page) };
+        (pages as *mut libc::c_void, self.map_len - 2 * self.page)
+    }
+
+    /// The start of the secret bytes, which end where the second guard page begins.
+    fn data(&self) -> *mut u8 {
+        // SAFETY: the offset is within the mapping.
+        unsafe { self.map.add(self.map_len - self.page - self.len) }
+    }
+
+    /// Access the secret bytes.
+    pub fn expose_secret(&self) -> &[u8] {
+        // SAFETY: the secret bytes are mapped readable and owned by `self`.
+        unsafe { core::slice::from_raw_parts(self.data(), self.len) }
+    }
+
+    /// The number of secret bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no secret bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Drop for LockedSecret {
+    fn drop(&mut self) {
+        let (pages, data_len) = self.data_pages();
+        // SAFETY: the mapping is owned by `self` and the secret bytes are writable.
+        unsafe {
+            wipe(core::slice::from_raw_parts_mut(self.data(), self.len));
+            libc::munlock(pages, data_len);
+            libc::munmap(self.map as *mut libc::c_void, self.map_len);
+        }
+    }
+}
+
+impl fmt::Debug for LockedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LockedSecret({} bytes [REDACTED])", self.len)
+    }
+}
+
+impl PartialEq for LockedSecret {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(self.expose_secret(), other.expose_secret())
+    }
+}
+
+impl Eq for LockedSecret {}
+
+#[cfg(test)]
+mod test {
+    use super::LockedSecret;
+    use crate::mock::Replay;
+    use std::fs;
+
+    /// The amount of locked memory of this process in kB.
+    fn locked_kb() -> usize {
+        let status = fs::read_to_string("/proc/self/status").unwrap();
+        let line = status.lines().find(|l| l.starts_with("VmLck:")).unwrap();
+        line.split_whitespace().nth(1).unwrap().parse().unwrap()
+    }
+
+    /// The permissions and flags of the mapping containing `addr`, as listed in
+    /// `/proc/self/smaps`.
+    fn mapping(addr: usize) -> (String, String) {
+        let smaps = fs::read_to_string("/proc/self/smaps").unwrap();
+        let mut lines = smaps.lines();
+        while let Some(line) = lines.next() {
+            let mut fields = line.split_whitespace();
+            let (range, perms) = match (fields.next(), fields.next()) {
+                (Some(range), Some(perms)) if range.contains('-') => (range, perms),
+                _ => continue,
+            };
+            let (start, end) = range.split_once('-').unwrap();
+            let start = usize::from_str_radix(start, 16).unwrap();
+            let end = usize::from_str_radix(end, 16).unwrap();
+            if (start..end).contains(&addr) {
+                let flags = lines.find(|l| l.starts_with("VmFlags:")).unwrap();
+                return (perms.to_owned(), flags.to_owned());
+            }
+        }
+        panic!("{:#x} is not mapped", addr);
+    }
+
+    // All checks are in one test, as the locked memory of the process is shared between threads.
+    #[test]
+    fn locks_and_fills() {
+        let input = [0x5a; 100];
+        let before = locked_kb();
+        let secret = LockedSecret::from_source(&mut Replay(&input), 100).unwrap();
+        assert_eq!(secret.expose_secret(), &input[..]);
+        assert!(locked_kb() >= before + 4);
+
+        let start = secret.expose_secret().as_ptr() as usize;
+        let end = start + secret.len();
+        assert_eq!(end % secret.page, 0);
+        let (perms, flags) = mapping(start);
+        assert_eq!(perms, "rw-p");
+        assert!(flags.split_whitespace().any(|f| f == "lo"), "{}", flags);
+        assert!(flags.split_whitespace().any(|f| f == "dd"), "{}", flags);
+        assert_eq!(mapping(end).0, "---p");
+        assert_eq!(mapping(end - secret.page - 1).0, "---p");
+
+        assert_eq!(
+            format!("{:?}", secret),
+            "LockedSecret(100 bytes [REDACTED])"
+        );
+        drop(secret);
+        assert_eq!(locked_kb(), before);
+
+        // Failures of the source unmap the memory again.
+        assert!(LockedSecret::from_source(&mut Replay(&input), 101).is_err());
+        assert_eq!(locked_kb(), before);
+
+        let _ = LockedSecret::new(32).map(|secret| {
+            assert_ne!(secret.expose_secret(), &[0; 32]);
+            assert_ne!(secret, LockedSecret::new(32).unwrap());
+        });
+    }
+}
@@ -0,0 +1,96 @@
+//! Deterministic entropy sources for use in tests.
+// Not every combination of features uses every helper in here.
+#![allow(dead_code)]
+
+extern crate std;
+
+use crate::{EntropySource, ErrorCode};
+use std::vec::Vec;
+
+/// Hands out the bytes of a fixed buffer and fails once they run out.
+pub(crate) struct Replay<'a>(pub(crate) &'a [u8]);
+
+impl EntropySource for Replay<'_> {
+    fn try_next_u16(&mut self) -> Result<u16, ErrorCode> {
+        let mut bytes = [0; 2];
+        self.try_fill_bytes(&mut bytes)?;
+        Ok(u16::from_le_bytes(bytes))
+    }
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        let mut bytes = [0; 4];
+        self.try_fill_bytes(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        let mut bytes = [0; 8];
+        self.try_fill_bytes(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        if dest.len() > self.0.len() {
+            return Err(ErrorCode::HardwareFailure);
+        }
+        let (head, tail) = self.0.split_at(dest.len());
+        dest.copy_from_slice(head);
+        self.0 = tail;
+        Ok(())
+    }
+}
+
+/// Decode a hexadecimal test vector.
+pub(crate) fn hex(s: &str) -> Vec<u8> {
+    let digit = |c: u8| (c as char).to_digit(16).expect("not a hex digit") as u8;
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| digit(pair[0]) << 4 | digit(pair[1]))
+        .collect()
+}
+
+/// Hands out the bytes of a fixed buffer one at a time when used without blocking.
+pub(crate) struct Trickle<'a>(pub(crate) Replay<'a>);
+
+impl EntropySource for Trickle<'_> {
+    fn try_next_u16(&mut self) -> Result<u16, ErrorCode> {
+        self.0.try_next_u16()
+    }
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        self.0.try_next_u32()
+    }
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        self.0.try_next_u64()
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        self.0.try_fill_bytes(dest)
+    }
+    fn try_fill_bytes_nonblocking(&mut self, dest: &mut [u8]) -> Result<usize, ErrorCode> {
+        let len = dest.len().min(1).min((self.0).0.len());
+        self.0.try_fill_bytes(&mut dest[..len])?;
+        Ok(len)
+    }
+}
+
+/// A deterministic pseudo-random source (SplitMix64) for statistical tests.
+pub(crate) struct SplitMix(pub(crate) u64);
+
+impl EntropySource for SplitMix {
+    fn try_next_u16(&mut self) -> Result<u16, ErrorCode> {
+        self.try_next_u64().map(|x| x as u16)
+    }
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        self.try_next_u64().map(|x| x as u32)
+    }
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Ok(z ^ (z >> 31))
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        for chunk in dest.chunks_mut(8) {
+            let word = self.try_next_u64()?.to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
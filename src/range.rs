@@ -0,0 +1,122 @@
+//! Unbiased sampling of integers from ranges.
+use crate::{EntropySource, ErrorCode};
+use core::ops::{Bound, RangeBounds};
+
+/// Generate a uniformly distributed integer in `0..s` with Lemire's nearly divisionless method.
+///
+/// See Daniel Lemire, "Fast Random Integer Generation in an Interval", ACM Transactions on
+/// Modeling and Computer Simulation 29 (1), 2019.
+macro_rules! lemire {
+    ($name:ident, $ty:ty, $wide:ty, $next:ident) => {
+        fn $name<S: EntropySource>(source: &mut S, s: $ty) -> Result<$ty, ErrorCode> {
+            let mut m = <$wide>::from(source.$next()?) * <$wide>::from(s);
+            let mut l = m as $ty;
+            if l < s {
+                // `2^BITS mod s`, the number of low products that would bias the result.
+                let t = s.wrapping_neg() % s;
+                while l < t {
+                    m = <$wide>::from(source.$next()?) * <$wide>::from(s);
+                    l = m as $ty;
+                }
+            }
+            Ok((m >> <$ty>::BITS) as $ty)
+        }
+    };
+}
+
+lemire!(below_u16, u16, u32, try_next_u16);
+lemire!(below_u32, u32, u64, try_next_u32);
+lemire!(below_u64, u64, u128, try_next_u64);
+
+/// Generate a uniformly distributed integer in `low..=high` using the narrowest draws that cover
+/// the range.
+pub(crate) fn gen_inclusive<S: EntropySource>(
+    source: &mut S,
+    low: u64,
+    high: u64,
+) -> Result<u64, ErrorCode> {
+    let offset = match high - low {
+        0 => 0,
+        max if max < u64::from(u16::MAX) => u64::from(below_u16(source, max as u16 + 1)?),
+        max if max == u64::from(u16::MAX) => u64::from(source.try_next_u16()?),
+        max if max < u64::from(u32::MAX) => u64::from(below_u32(source, max as u32 + 1)?),
+        max if max == u64::from(u32::MAX) => u64::from(source.try_next_u32()?),
+        max if max < u64::MAX => below_u64(source, max + 1)?,
+        _ => source.try_next_u64()?,
+    };
+    Ok(low + offset)
+}
+
+/// Convert `range` to inclusive bounds within `0..=max`.
+///
+/// # Panics
+///
+/// This function panics if `range` is empty.
+pub(crate) fn inclusive_bounds<T, R>(range: &R, max: u64) -> (u64, u64)
+where
+    T: Copy + Into<u64>,
+    R: RangeBounds<T>,
+{
+    let low = match range.start_bound() {
+        Bound::Included(&low) => Some(low.into()),
+        Bound::Excluded(&low) => low.into().checked_add(1),
+        Bound::Unbounded => Some(0),
+    };
+    let high = match range.end_bound() {
+        Bound::Included(&high) => Some(high.into()),
+        Bound::Excluded(&high) => high.into().checked_sub(1),
+        Bound::Unbounded => Some(max),
+    };
+    match (low, high) {
+        (Some(low), Some(high)) if low <= high => (low, high),
+        _ => panic!("cannot sample from an empty range"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{gen_inclusive, inclusive_bounds};
+    use crate::mock::Replay;
+
+    #[test]
+    fn rejects_biased_draws() {
+        // 0 falls into the biased zone of `0..6`, while 0xffff maps to 5.
+        let mut source = Replay(&[0, 0, 0xff, 0xff]);
+        assert_eq!(gen_inclusive(&mut source, 10, 15).unwrap(), 15);
+        assert!(source.0.is_empty());
+    }
+
+    #[test]
+    fn uses_narrowest_draws() {
+        let mut source = Replay(&[0x01, 0x80]);
+        assert_eq!(gen_inclusive(&mut source, 0, 999).unwrap(), 500);
+        let mut source = Replay(&[0x01, 0x00, 0x00, 0x80]);
+        assert_eq!(gen_inclusive(&mut source, 1, 200_000).unwrap(), 100_001);
+        let mut source = Replay(&[0xff, 0xff]);
+        assert_eq!(
+            gen_inclusive(&mut source, 7, 7 + 0xffff).unwrap(),
+            7 + 0xffff
+        );
+        let mut source = Replay(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(
+            gen_inclusive(&mut source, 0, u64::MAX).unwrap(),
+            0x0807_0605_0403_0201
+        );
+        // A single value needs no draws at all.
+        assert_eq!(gen_inclusive(&mut Replay(&[]), 42, 42).unwrap(), 42);
+    }
+
+    #[test]
+    fn converts_bounds() {
+        assert_eq!(inclusive_bounds(&(3u32..7), u32::MAX.into()), (3, 6));
+        assert_eq!(inclusive_bounds(&(..=7u32), u32::MAX.into()), (0, 7));
+        assert_eq!(inclusive_bounds(&(5u64..), u64::MAX), (5, u64::MAX));
+        assert_eq!(inclusive_bounds::<u64, _>(&(..), u64::MAX), (0, u64::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "empty range")]
+    fn rejects_empty_ranges() {
+        inclusive_bounds(&(5u32..5), u32::MAX.into());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::fork::ForkGuard;
+use crate::{EntropySource, ErrorCode, RdRand};
+use std::boxed::Box;
+use std::io::{self, BufRead, Read};
+use std::vec;
+
+/// The default capacity of the internal buffer.
+const DEFAULT_CAPACITY: usize = 4096;
+
+/// The smallest capacity of the internal buffer, a single word of the source.
+const MIN_CAPACITY: usize = 8;
+
+/// An adapter implementing [`io::Read`] and [`io::BufRead`] on top of [`RdRand`] (or another
+/// [`EntropySource`]).
+///
+/// The reader never reaches the end of the stream, so combine it with [`Read::take`] to copy a
+/// limited amount of random data with [`io::copy`]. Reads at least as large as the internal
+/// buffer bypass it entirely.
+///
+/// Errors are reported as [`io::Error`]s of kind [`io::ErrorKind::Unsupported`] or
+/// [`io::ErrorKind::Other`], which wrap the [`ErrorCode`] that can be recovered with
+/// [`io::Error::get_ref`] and `downcast_ref`.
+///
+/// This reader is only available with the `std` feature enabled.
+pub struct RdReader<S = RdRand> {
+    source: S,
+    buffer: Box<[u8]>,
+    /// The bytes `buffer[pos..filled]` have not been read yet.
+    pos: usize,
+    filled: usize,
+    fork: ForkGuard,
+}
+
+impl RdReader {
+    /// Create a new reader producing data from [`RdRand`].
+    ///
+    /// An error is returned if the `rdrand` instruction is not supported.
+    pub fn new() -> Result<Self, ErrorCode> {
+        RdRand::new().map(Self::with_source)
+    }
+}
+
+impl<S: EntropySource> RdReader<S> {
+    /// Create a new reader producing data from `source`.
+    pub fn with_source(source: S) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, source)
+    }
+
+    /// Create a new reader producing data from `source` with an internal buffer of `capacity`
+    /// bytes.
+    ///
+    /// The capacity is raised to 8 bytes if it is smaller, so that [`fill_buf`] never returns an
+    /// empty slice.
+    ///
+    /// [`fill_buf`]: BufRead::fill_buf
+    pub fn with_capacity(capacity: usize, source: S) -> Self {
+        RdReader {
+            source,
+            buffer: vec![0; capacity.max(MIN_CAPACITY)].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+            fork: ForkGuard::new(),
+        }
+    }
+
+    /// The capacity of the internal buffer.
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Unwrap the source, discarding any buffered data.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    /// Discard the buffered data if the process has forked since it was produced.
+    fn discard_after_fork(&mut self) {
+        if self.fork.forked() {
+            self.pos = 0;
+            self.filled = 0;
+            self.fork.reset();
+        }
+    }
+}
+
+impl<S: EntropySource> Read for RdReader<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.discard_after_fork();
+        if self.pos == self.filled && buf.len() >= self.buffer.len() {
+            self.source.try_fill_bytes(buf)?;
+            return Ok(buf.len());
+        }
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<S: EntropySource> BufRead for RdReader<S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.discard_after_fork();
+        if self.pos == self.filled {
+            self.source.try_fill_bytes(&mut self.buffer)?;
+            self.pos = 0;
+            self.filled = self.buffer.len();
+        }
+        Ok(&self.buffer[self.pos..self.filled])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.filled);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RdReader;
+    use crate::mock::Replay;
+    use crate::ErrorCode;
+    use std::io::{self, BufRead, Read};
+
+    fn counting() -> [u8; 64] {
+        let mut input = [0; 64];
+        for (i, b) in input.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        input
+    }
+
+    #[test]
+    fn buffers_reads() {
+        let input = counting();
+        let mut reader = RdReader::with_capacity(16, Replay(&input));
+        let mut buf = [0; 5];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0, 1, 2, 3, 4]);
+        assert_eq!(reader.fill_buf().unwrap(), &input[5..16]);
+        reader.consume(11);
+        // Large reads bypass the buffer.
+        let mut buf = [0; 20];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &input[16..36]);
+        assert_eq!(reader.fill_buf().unwrap(), &input[36..52]);
+    }
+
+    #[test]
+    fn clamps_capacity() {
+        let input = counting();
+        let mut reader = RdReader::with_capacity(0, Replay(&input));
+        assert_eq!(reader.capacity(), 8);
+        assert_eq!(reader.fill_buf().unwrap(), &input[..8]);
+    }
+
+    #[test]
+    fn maps_errors() {
+        let mut reader = RdReader::with_source(Replay(&[]));
+        let error = reader.read(&mut [0; 1]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Other);
+        let code = error.get_ref().unwrap().downcast_ref::<ErrorCode>();
+        assert!(matches!(code, Some(ErrorCode::HardwareFailure)));
+
+        let error: io::Error = ErrorCode::UnsupportedInstruction.into();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn copies() {
+        let input = counting();
+        let reader = RdReader::with_capacity(8, Replay(&input));
+        let mut output = Vec::new();
+        assert_eq!(io::copy(&mut reader.take(60), &mut output).unwrap(), 60);
+        assert_eq!(&output[..], &input[..60]);
+    }
+
+    #[test]
+    fn rdreader_works() {
+        let _ = RdReader::new().map(|reader| {
+            let copied = io::copy(&mut reader.take(100_000), &mut io::sink()).unwrap();
+            assert_eq!(copied, 100_000);
+        });
+    }
+}
@@ -0,0 +1,375 @@
+use crate::fork::ForkGuard;
+use crate::{busy_loop_fail, EntropySource, ErrorCode, RdSeed};
+use rand_core_06::block::BlockRngCore;
+use rand_core_06::impls::fill_via_u32_chunks;
+use rand_core_06::{CryptoRng, Error, RngCore, SeedableRng};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// A wrapper around a block generator `R` that reseeds it from [`RdSeed`] (or another
+/// [`EntropySource`]).
+///
+/// The generator is reseeded once it has produced the configured number of bytes since the last
+/// reseed, or, with the `std` feature enabled, once the configured amount of time has passed.
+///
+/// The source may fail to produce the seed (for example, `rdseed` may run out of entropy under
+/// heavy contention). By default the wrapper then keeps using the current state of the generator,
+/// records the failure (see [`last_reseed_error`]) and retries on every subsequent block until
+/// the reseed succeeds. With [`set_continue_on_failure`] set to `false`, the failure is returned
+/// as an error instead.
+///
+/// This wrapper is only available with the `rand_core_06` feature enabled.
+///
+/// [`last_reseed_error`]: Reseeding::last_reseed_error
+/// [`set_continue_on_failure`]: Reseeding::set_continue_on_failure
+pub struct Reseeding<R: BlockRngCore, S = RdSeed> {
+    core: R,
+    results: R::Results,
+    /// The items `results[index..]` have not been handed out yet.
+    index: usize,
+    source: S,
+    threshold: u64,
+    bytes_since_reseed: u64,
+    #[cfg(feature = "std")]
+    interval: Option<Duration>,
+    #[cfg(feature = "std")]
+    last_reseed: Instant,
+    continue_on_failure: bool,
+    last_reseed_error: Option<ErrorCode>,
+    failed_reseeds: u64,
+    fork: ForkGuard,
+}
+
+impl<R> Reseeding<R>
+where
+    R: BlockRngCore<Item = u32> + SeedableRng,
+{
+    /// Create a new generator seeded from [`RdSeed`] and reseeded after producing `threshold`
+    /// bytes.
+    ///
+    /// An error is returned if the `rdseed` instruction is not supported or if it fails to produce
+    /// the initial seed.
+    pub fn new(threshold: u64) -> Result<Self, ErrorCode> {
+        Self::with_source(RdSeed::new()?, threshold)
+    }
+}
+
+impl<R, S> Reseeding<R, S>
+where
+    R: BlockRngCore<Item = u32> + SeedableRng,
+    S: EntropySource,
+{
+    /// Create a new generator seeded from `source` and reseeded after producing `threshold`
+    /// bytes.
+    pub fn with_source(mut source: S, threshold: u64) -> Result<Self, ErrorCode> {
+        let core = seed_from(&mut source)?;
+        let results = R::Results::default();
+        Ok(Reseeding {
+            core,
+            index: results.as_ref().len(),
+            results,
+            source,
+            threshold,
+            bytes_since_reseed: 0,
+            #[cfg(feature = "std")]
+            interval: None,
+            #[cfg(feature = "std")]
+            last_reseed: Instant::now(),
+            continue_on_failure: true,
+            last_reseed_error: None,
+            failed_reseeds: 0,
+            fork: ForkGuard::new(),
+        })
+    }
+
+    /// Set the time after which the generator is reseeded.
+    ///
+    /// The time is checked only when a new block is generated. By default there is no time
+    /// budget.
+    ///
+    /// This method is only available with the `std` feature enabled.
+    #[cfg(feature = "std")]
+    pub fn set_reseed_interval(&mut self, interval: Option<Duration>) {
+        self.interval = interval;
+    }
+
+    /// Set whether the generator should keep producing output when a reseed fails.
+    ///
+    /// This is enabled by default.
+    pub fn set_continue_on_failure(&mut self, enabled: bool) {
+        self.continue_on_failure = enabled;
+    }
+
+    /// The error produced by the most recent reseed, if it failed.
+    ///
+    /// This is reset to `None` once a reseed succeeds.
+    pub fn last_reseed_error(&self) -> Option<ErrorCode> {
+        self.last_reseed_error
+    }
+
+    /// The total number of reseeds that have failed.
+    pub fn failed_reseeds(&self) -> u64 {
+        self.failed_reseeds
+    }
+
+    /// Reseed the generator from the source.
+    ///
+    /// Any buffered output is discarded if the reseed succeeds.
+    pub fn reseed(&mut self) -> Result<(), ErrorCode> {
+        match seed_from(&mut self.source) {
+            Ok(core) => {
+                self.core = core;
+                self.index = self.results.as_ref().len();
+                self.bytes_since_reseed = 0;
+                #[cfg(feature = "std")]
+                {
+                    self.last_reseed = Instant::now();
+                }
+                self.last_reseed_error = None;
+                Ok(())
+            }
+            Err(code) => {
+                self.last_reseed_error = Some(code);
+                self.failed_reseeds += 1;
+                Err(code)
+            }
+        }
+    }
+
+    fn reseed_due(&self) -> bool {
+        #[cfg(feature = "std")]
+        {
+            if let Some(interval) = self.interval {
+                if self.last_reseed.elapsed() >= interval {
+                    return true;
+                }
+            }
+        }
+        self.bytes_since_reseed >= self.threshold
+    }
+
+    fn generate(&mut self) -> Result<(), ErrorCode> {
+        if self.reseed_due() {
+            if let Err(code) = self.reseed() {
+                if !self.continue_on_failure {
+                    return Err(code);
+                }
+            }
+        }
+        self.core.generate(&mut self.results);
+        self.index = 0;
+        self.bytes_since_reseed += 4 * self.results.as_ref().len() as u64;
+        Ok(())
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// An error is returned if a reseed fails and the generator was configured to not continue in
+    /// that case, or if the process has forked and the generator fails to be reseeded. Without a
+    /// reseed, the child process would produce the same output as its parent.
+    pub fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        if self.fork.forked() {
+            self.reseed()?;
+            self.fork.reset();
+        }
+        let mut filled = 0;
+        while filled < dest.len() {
+            if self.index >= self.results.as_ref().len() {
+                self.generate()?;
+            }
+            let (consumed, written) =
+                fill_via_u32_chunks(&self.results.as_ref()[self.index..], &mut dest[filled..]);
+            self.index += consumed;
+            filled += written;
+        }
+        Ok(())
+    }
+}
+
+fn seed_from<R: SeedableRng, S: EntropySource>(source: &mut S) -> Result<R, ErrorCode> {
+    let mut seed = R::Seed::default();
+    source.try_fill_bytes(seed.as_mut())?;
+    Ok(R::from_seed(seed))
+}
+
+impl<R, S> EntropySource for Reseeding<R, S>
+where
+    R: BlockRngCore<Item = u32> + SeedableRng,
+    S: EntropySource,
+{
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        Reseeding::try_fill_bytes(self, dest)
+    }
+}
+
+impl<R, S> RngCore for Reseeding<R, S>
+where
+    R: BlockRngCore<Item = u32> + SeedableRng,
+    S: EntropySource,
+{
+    /// Generate a single random `u32` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if a reseed fails and the generator was configured to not continue
+    /// in that case.
+    fn next_u32(&mut self) -> u32 {
+        match EntropySource::try_next_u32(self) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Generate a single random `u64` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic if a reseed fails and the generator was configured to not continue
+    /// in that case.
+    fn next_u64(&mut self) -> u64 {
+        match EntropySource::try_next_u64(self) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_fill_bytes` would return an error.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match Reseeding::try_fill_bytes(self, dest) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        Reseeding::try_fill_bytes(self, dest).map_err(Into::into)
+    }
+}
+
+impl<R, S> CryptoRng for Reseeding<R, S>
+where
+    R: BlockRngCore<Item = u32> + SeedableRng + CryptoRng,
+    S: EntropySource + CryptoRng,
+{
+}
+
+#[cfg(feature = "rand_core_09")]
+impl<R, S> rand_core_09::TryRngCore for Reseeding<R, S>
+where
+    R: BlockRngCore<Item = u32> + SeedableRng,
+    S: EntropySource,
+{
+    type Error = ErrorCode;
+
+    /// Generate a single random `u32` value.
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        EntropySource::try_next_u32(self)
+    }
+
+    /// Generate a single random `u64` value.
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        EntropySource::try_next_u64(self)
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        Reseeding::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_09")]
+impl<R, S> rand_core_09::TryCryptoRng for Reseeding<R, S>
+where
+    R: BlockRngCore<Item = u32> + SeedableRng + CryptoRng,
+    S: EntropySource + rand_core_09::TryCryptoRng,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::Reseeding;
+    use crate::fork::ForkGuard;
+    use crate::mock::Replay;
+    use crate::{EntropySource, RdSeed};
+    use rand_core_06::block::BlockRngCore;
+    use rand_core_06::SeedableRng;
+
+    /// Produces consecutive integers starting at the seed.
+    struct Counter(u32);
+
+    impl BlockRngCore for Counter {
+        type Item = u32;
+        type Results = [u32; 4];
+        fn generate(&mut self, results: &mut [u32; 4]) {
+            for r in results {
+                *r = self.0;
+                self.0 += 1;
+            }
+        }
+    }
+
+    impl SeedableRng for Counter {
+        type Seed = [u8; 4];
+        fn from_seed(seed: [u8; 4]) -> Self {
+            Counter(u32::from_le_bytes(seed))
+        }
+    }
+
+    #[test]
+    fn reseeds_after_threshold() {
+        let seeds = [0, 0, 0, 0, 100, 0, 0, 0];
+        let mut rng = Reseeding::<Counter, _>::with_source(Replay(&seeds), 16).unwrap();
+        for &expected in &[0, 1, 2, 3, 100, 101] {
+            assert_eq!(rng.try_next_u32().unwrap(), expected);
+        }
+        assert_eq!(rng.failed_reseeds(), 0);
+    }
+
+    #[test]
+    fn continues_after_failed_reseed() {
+        let seeds = [0, 0, 0, 0];
+        let mut rng = Reseeding::<Counter, _>::with_source(Replay(&seeds), 16).unwrap();
+        for expected in 0..10 {
+            assert_eq!(rng.try_next_u32().unwrap(), expected);
+        }
+        assert!(rng.last_reseed_error().is_some());
+        assert_eq!(rng.failed_reseeds(), 2);
+    }
+
+    #[test]
+    fn reports_failed_reseed() {
+        let seeds = [0, 0, 0, 0];
+        let mut rng = Reseeding::<Counter, _>::with_source(Replay(&seeds), 16).unwrap();
+        rng.set_continue_on_failure(false);
+        rng.try_fill_bytes(&mut [0; 16]).unwrap();
+        assert!(rng.try_fill_bytes(&mut [0; 1]).is_err());
+        assert_eq!(rng.failed_reseeds(), 1);
+    }
+
+    #[test]
+    fn reseeds_after_fork() {
+        let seeds = [0, 0, 0, 0, 100, 0, 0, 0];
+        let mut rng = Reseeding::<Counter, _>::with_source(Replay(&seeds), 1024).unwrap();
+        assert_eq!(rng.try_next_u32().unwrap(), 0);
+        rng.fork = ForkGuard::stale();
+        assert_eq!(rng.try_next_u32().unwrap(), 100);
+        // Continuing without a reseed is not an option after a fork.
+        rng.fork = ForkGuard::stale();
+        assert!(rng.try_next_u32().is_err());
+        assert!(rng.try_next_u32().is_err());
+    }
+
+    #[test]
+    fn rdseed_seed_works() {
+        let _ = RdSeed::new().map(|mut r| {
+            let _: Counter = r.seed().unwrap();
+            let mut rng = Reseeding::<Counter>::new(16).unwrap();
+            rng.try_fill_bytes(&mut [0; 100]).unwrap();
+        });
+    }
+}
@@ -0,0 +1,238 @@
+//! Buffers for secret random bytes that are wiped when dropped.
+use crate::{EntropySource, ErrorCode, RdSeed};
+use core::fmt;
+use core::sync::atomic::{compiler_fence, Ordering};
+#[cfg(feature = "std")]
+use std::boxed::Box;
+#[cfg(feature = "std")]
+use std::vec;
+
+/// Overwrite `bytes` with zeros in a way that the compiler does not optimize away.
+pub(crate) fn wipe(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        // SAFETY: `byte` is a valid and aligned reference.
+        unsafe { core::ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Compare `a` and `b` in time that only depends on their lengths.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y));
+    // SAFETY: `diff` is a valid local variable. The volatile read keeps the compiler from
+    // turning the loop above into an early exit.
+    unsafe { core::ptr::read_volatile(&diff) == 0 }
+}
+
+/// A buffer for intermediate random data that is wiped when it goes out of scope.
+///
+/// This is a best effort: copies the compiler makes in registers or on the stack are not wiped.
+pub(crate) struct Wiped<const N: usize>(pub(crate) [u8; N]);
+
+impl<const N: usize> Drop for Wiped<N> {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+/// A fixed-size buffer of secret random bytes, such as a key, that is wiped when dropped.
+///
+/// The buffer is filled in place by [`RdSeed`] (or another [`EntropySource`]). Its `Debug`
+/// implementation does not reveal the contents and comparisons take constant time.
+///
+/// The bytes are stored inline, so moving the value may leave copies of them behind that are not
+/// wiped. Use [`SecretVec`] to keep the bytes at a single location on the heap.
+pub struct SecretBytes<const N: usize>([u8; N]);
+
+impl<const N: usize> SecretBytes<N> {
+    /// Generate `N` secret bytes with [`RdSeed`].
+    ///
+    /// An error is returned if the `rdseed` instruction is not supported or fails.
+    pub fn new() -> Result<Self, ErrorCode> {
+        Self::from_source(&mut RdSeed::new()?)
+    }
+
+    /// Generate `N` secret bytes with `source`.
+    pub fn from_source<S: EntropySource>(source: &mut S) -> Result<Self, ErrorCode> {
+        let mut secret = SecretBytes([0; N]);
+        source.try_fill_bytes(&mut secret.0)?;
+        Ok(secret)
+    }
+
+    /// Access the secret bytes.
+    pub fn expose_secret(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> Drop for SecretBytes<N> {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+impl<const N: usize> fmt::Debug for SecretBytes<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes<{}>([REDACTED])", N)
+    }
+}
+
+impl<const N: usize> PartialEq for SecretBytes<N> {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.0, &other.0)
+    }
+}
+
+impl<const N: usize> Eq for SecretBytes<N> {}
+
+/// A heap-allocated buffer of secret random bytes that is wiped when dropped.
+///
+/// The buffer is allocated once and filled in place by [`RdSeed`] (or another
+/// [`EntropySource`]), so no copies of the bytes are left behind. Its `Debug` implementation does
+/// not reveal the contents and comparisons take constant time, except for the length, which is
+/// not considered secret.
+///
+/// This type is only available with the `std` feature enabled.
+#[cfg(feature = "std")]
+pub struct SecretVec(Box<[u8]>);
+
+#[cfg(feature = "std")]
+impl SecretVec {
+    /// Generate `len` secret bytes with [`RdSeed`].
+    ///
+    /// An error is returned if the `rdseed` instruction is not supported or fails.
+    pub fn new(len: usize) -> Result<Self, ErrorCode> {
+        Self::from_source(&mut RdSeed::new()?, len)
+    }
+
+    /// Generate `len` secret bytes with `source`.
+    pub fn from_source<S: EntropySource>(source: &mut S, len: usize) -> Result<Self, ErrorCode> {
+        let mut secret = SecretVec(vec![0; len].into_boxed_slice());
+        source.try_fill_bytes(&mut secret.0)?;
+        Ok(secret)
+    }
+
+    /// Access the secret bytes.
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The number of secret bytes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no secret bytes.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for SecretVec {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for SecretVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretVec({} bytes [REDACTED])", self.0.len())
+    }
+}
+
+#[cfg(feature = "std")]
+impl PartialEq for SecretVec {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Eq for SecretVec {}
+
+#[cfg(feature = "zeroize")]
+impl<const N: usize> zeroize::Zeroize for SecretBytes<N> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const N: usize> zeroize::ZeroizeOnDrop for SecretBytes<N> {}
+
+#[cfg(all(feature = "zeroize", feature = "std"))]
+impl zeroize::Zeroize for SecretVec {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(all(feature = "zeroize", feature = "std"))]
+impl zeroize::ZeroizeOnDrop for SecretVec {}
+
+#[cfg(test)]
+mod test {
+    use super::{constant_time_eq, wipe, SecretBytes};
+    use crate::mock::Replay;
+
+    #[test]
+    fn wipes() {
+        let mut bytes = [0xaa; 13];
+        wipe(&mut bytes);
+        assert_eq!(bytes, [0; 13]);
+    }
+
+    #[test]
+    fn compares() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secrets"));
+
+        let input = [1, 2, 3, 1, 2, 3, 1, 2, 4];
+        let mut source = Replay(&input);
+        let a = SecretBytes::<3>::from_source(&mut source).unwrap();
+        let b = SecretBytes::<3>::from_source(&mut source).unwrap();
+        let c = SecretBytes::<3>::from_source(&mut source).unwrap();
+        assert_eq!(a.expose_secret(), &[1, 2, 3]);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert!(SecretBytes::<3>::from_source(&mut source).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn redacts() {
+        let secret = SecretBytes::<4>::from_source(&mut Replay(&[0x42; 4])).unwrap();
+        assert_eq!(format!("{:?}", secret), "SecretBytes<4>([REDACTED])");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn secret_vec() {
+        use super::SecretVec;
+        let input = [7; 40];
+        let secret = SecretVec::from_source(&mut Replay(&input), 40).unwrap();
+        assert_eq!(secret.expose_secret(), &input[..]);
+        assert_eq!(format!("{:?}", secret), "SecretVec(40 bytes [REDACTED])");
+        let _ = SecretVec::new(32).map(|other| assert_ne!(secret, other));
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroizes() {
+        use zeroize::Zeroize;
+        let mut secret = SecretBytes::<8>::from_source(&mut Replay(&[0xff; 8])).unwrap();
+        secret.zeroize();
+        assert_eq!(secret.expose_secret(), &[0; 8]);
+    }
+
+    #[test]
+    fn secret_bytes_work() {
+        let _ = SecretBytes::<32>::new().map(|secret| assert_ne!(secret.expose_secret(), &[0; 32]));
+    }
+}
@@ -0,0 +1,221 @@
+#[cfg(feature = "rand_core_06")]
+use crate::busy_loop_fail;
+use crate::cbc_mac;
+use crate::{EntropySource, ErrorCode, RdRand};
+use aes::Block;
+#[cfg(feature = "rand_core_06")]
+use rand_core_06::{CryptoRng, Error, RngCore};
+
+/// The number of 128-bit `rdrand` samples condensed into a single 128-bit seed.
+///
+/// The DRNG is reseeded after it produces at most 511 128-bit samples, so any 512 consecutive
+/// samples span at least one reseed.
+const SAMPLES_PER_SEED: usize = 512;
+
+/// A seed-grade random bit generator built on top of the `rdrand` instruction.
+///
+/// This generator implements the method described in Intel’s DRNG software implementation guide
+/// for obtaining seed-grade entropy on processors that support `rdrand` but not `rdseed`: every
+/// 128 bits of output are obtained by condensing 512 128-bit `rdrand` samples with AES-CBC-MAC,
+/// keyed by another `rdrand` sample. Drawing that many samples forces the DRNG to reseed from its
+/// entropy source in the meantime.
+///
+/// This makes the generator several orders of magnitude slower than [`RdRand`]. Since every
+/// method call condenses at least one full 128-bit block, prefer `try_fill_bytes` over the
+/// methods generating a single integer.
+///
+/// The API mirrors that of [`RdSeed`](crate::RdSeed), so this generator can be used in its stead.
+///
+/// This generator is only available with the `seed-from-rdrand` feature enabled.
+#[derive(Clone, Copy)]
+pub struct SeedFromRdRand(RdRand);
+
+impl SeedFromRdRand {
+    /// Create a new instance of the random number generator.
+    ///
+    /// This constructor checks whether the CPU the program is running on supports the `rdrand`
+    /// instruction. If the instruction is not supported, an error is returned.
+    pub fn new() -> Result<Self, ErrorCode> {
+        RdRand::new().map(SeedFromRdRand)
+    }
+
+    /// Create a new instance of the random number generator.
+    ///
+    /// # Safety
+    ///
+    /// This constructor is unsafe because it doesn't check that the CPU supports the `rdrand`
+    /// instruction, but devolves this responsibility to the caller.
+    pub unsafe fn new_unchecked() -> Self {
+        SeedFromRdRand(RdRand::new_unchecked())
+    }
+
+    /// Generate a single random `u16` value.
+    ///
+    /// In case `Err` is returned, the caller should assume that a non-recoverable failure has
+    /// occured and use another random number genrator instead.
+    pub fn try_next_u16(&self) -> Result<u16, ErrorCode> {
+        let block = self.try_next_block()?;
+        Ok(u16::from_ne_bytes([block[0], block[1]]))
+    }
+
+    /// Generate a single random `u32` value.
+    ///
+    /// In case `Err` is returned, the caller should assume that a non-recoverable failure has
+    /// occured and use another random number genrator instead.
+    pub fn try_next_u32(&self) -> Result<u32, ErrorCode> {
+        let block = self.try_next_block()?;
+        Ok(u32::from_ne_bytes([block[0], block[1], block[2], block[3]]))
+    }
+
+    /// Generate a single random `u64` value.
+    ///
+    /// In case `Err` is returned, the caller should assume that a non-recoverable failure has
+    /// occured and use another random number genrator instead.
+    pub fn try_next_u64(&self) -> Result<u64, ErrorCode> {
+        let block = self.try_next_block()?;
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&block[..8]);
+        Ok(u64::from_ne_bytes(bytes))
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// If an error is returned, the caller should assume that an non-recoverable hardware failure
+    /// has occured and use another random number genrator instead.
+    pub fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        for chunk in dest.chunks_mut(16) {
+            let block = self.try_next_block()?;
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        Ok(())
+    }
+
+    fn try_next_block(&self) -> Result<Block, ErrorCode> {
+        let mut rdrand = self.0;
+        condense(&mut rdrand, SAMPLES_PER_SEED)
+    }
+}
+
+/// Condense `samples` 128-bit samples from `source` with AES-CBC-MAC, keyed by a sample obtained
+/// from the same source.
+fn condense<S: EntropySource>(source: &mut S, samples: usize) -> Result<Block, ErrorCode> {
+    let mut key = [0; 16];
+    source.try_fill_bytes(&mut key)?;
+    cbc_mac::condense(&key, source, samples)
+}
+
+impl EntropySource for SeedFromRdRand {
+    fn try_next_u16(&mut self) -> Result<u16, ErrorCode> {
+        SeedFromRdRand::try_next_u16(self)
+    }
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        SeedFromRdRand::try_next_u32(self)
+    }
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        SeedFromRdRand::try_next_u64(self)
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        SeedFromRdRand::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl RngCore for SeedFromRdRand {
+    /// Generate a single random `u32` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_next_u32` would return an error.
+    fn next_u32(&mut self) -> u32 {
+        match self.try_next_u32() {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Generate a single random `u64` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_next_u64` would return an error.
+    fn next_u64(&mut self) -> u64 {
+        match self.try_next_u64() {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_fill_bytes` would return an error.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self.try_fill_bytes(dest) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.try_fill_bytes(dest).map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl CryptoRng for SeedFromRdRand {}
+
+#[cfg(feature = "rand_core_09")]
+impl rand_core_09::TryRngCore for SeedFromRdRand {
+    type Error = ErrorCode;
+
+    /// Generate a single random `u32` value.
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        SeedFromRdRand::try_next_u32(self)
+    }
+
+    /// Generate a single random `u64` value.
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        SeedFromRdRand::try_next_u64(self)
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        SeedFromRdRand::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_09")]
+impl rand_core_09::TryCryptoRng for SeedFromRdRand {}
+
+#[cfg(test)]
+mod test {
+    use super::{condense, SeedFromRdRand};
+    use crate::mock::{hex, Replay};
+
+    #[test]
+    fn condense_single_sample_is_aes() {
+        // With a single sample CBC-MAC degenerates to a single block encryption, so the FIPS-197
+        // AES-128 example vector applies.
+        let input = hex("000102030405060708090a0b0c0d0e0f00112233445566778899aabbccddeeff");
+        let block = condense(&mut Replay(&input), 1).unwrap();
+        assert_eq!(&block[..], &hex("69c4e0d86a7b0430d8cdb78070b4c55a")[..]);
+    }
+
+    #[test]
+    fn condense_reports_source_failure() {
+        let input = [0; 16 * 4];
+        assert!(condense(&mut Replay(&input), 4).is_err());
+        assert!(condense(&mut Replay(&input), 3).is_ok());
+    }
+
+    #[test]
+    fn seed_from_rdrand_works() {
+        let _ = SeedFromRdRand::new().map(|mut r| {
+            let mut buffer = [0; 17];
+            r.try_next_u64().unwrap();
+            r.try_fill_bytes(&mut buffer).unwrap();
+        });
+    }
+}
@@ -0,0 +1,150 @@
+//! Unbiased shuffling and sampling of sequences.
+use crate::range::gen_inclusive;
+use crate::{EntropySource, ErrorCode};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+/// Generate a uniformly distributed index in `low..=high`.
+fn index<S: EntropySource>(source: &mut S, low: usize, high: usize) -> Result<usize, ErrorCode> {
+    gen_inclusive(source, low as u64, high as u64).map(|i| i as usize)
+}
+
+/// Shuffle `slice` with the Fisher-Yates algorithm.
+pub(crate) fn shuffle<S: EntropySource, T>(
+    source: &mut S,
+    slice: &mut [T],
+) -> Result<(), ErrorCode> {
+    for i in (1..slice.len()).rev() {
+        slice.swap(i, index(source, 0, i)?);
+    }
+    Ok(())
+}
+
+/// Choose a uniformly distributed element of `slice`.
+pub(crate) fn choose<'a, S: EntropySource, T>(
+    source: &mut S,
+    slice: &'a [T],
+) -> Result<Option<&'a T>, ErrorCode> {
+    match slice.len() {
+        0 => Ok(None),
+        len => index(source, 0, len - 1).map(|i| slice.get(i)),
+    }
+}
+
+/// Choose `k` distinct indices in `0..n` in a uniformly random order.
+///
+/// Robert Floyd's algorithm is used when `k` is small compared to `n`, and a partial Fisher-Yates
+/// shuffle of all indices otherwise.
+///
+/// # Panics
+///
+/// This function panics if `k` is greater than `n`.
+#[cfg(feature = "std")]
+pub(crate) fn sample_indices<S: EntropySource>(
+    source: &mut S,
+    n: usize,
+    k: usize,
+) -> Result<Vec<usize>, ErrorCode> {
+    assert!(k <= n, "cannot sample {} indices out of {}", k, n);
+    if k.saturating_mul(k) <= n {
+        // Floyd's algorithm in the variant that also shuffles the indices: when `t` has been
+        // chosen before, `j` takes its place and `t` is moved to the end.
+        let mut indices = Vec::with_capacity(k);
+        for j in n - k..n {
+            let t = index(source, 0, j)?;
+            if let Some(pos) = indices.iter().position(|&x| x == t) {
+                indices[pos] = j;
+            }
+            indices.push(t);
+        }
+        Ok(indices)
+    } else {
+        let mut indices: Vec<usize> = (0..n).collect();
+        for i in 0..k {
+            let j = index(source, i, n - 1)?;
+            indices.swap(i, j);
+        }
+        indices.truncate(k);
+        Ok(indices)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{choose, shuffle};
+    use crate::mock::{Replay, SplitMix};
+
+    /// Check that all `counts` are within 5% of their mean.
+    fn assert_uniform(counts: &[u32]) {
+        let mean = counts.iter().sum::<u32>() as f64 / counts.len() as f64;
+        for &count in counts {
+            assert!((count as f64 - mean).abs() < mean * 0.05, "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn shuffle_is_uniform() {
+        let mut source = SplitMix(1);
+        // Count the permutations of 4 elements by their index in lexicographic order.
+        let mut counts = [0; 24];
+        for _ in 0..240_000 {
+            let mut items = [0, 1, 2, 3];
+            shuffle(&mut source, &mut items).unwrap();
+            let mut rank = 0;
+            for i in 0..4 {
+                let smaller_after = items[i + 1..].iter().filter(|&&x| x < items[i]).count();
+                rank = rank * (4 - i) + smaller_after;
+            }
+            counts[rank] += 1;
+        }
+        assert_uniform(&counts);
+    }
+
+    #[test]
+    fn choose_is_uniform() {
+        let mut source = SplitMix(2);
+        let items = [0, 1, 2, 3, 4, 5, 6];
+        let mut counts = [0; 7];
+        for _ in 0..70_000 {
+            counts[*choose(&mut source, &items).unwrap().unwrap()] += 1;
+        }
+        assert_uniform(&counts);
+        // Empty slices don't need any randomness.
+        assert_eq!(choose(&mut Replay(&[]), &[] as &[u8]).unwrap(), None);
+    }
+
+    #[test]
+    fn short_slices_need_no_randomness() {
+        shuffle(&mut Replay(&[]), &mut [1]).unwrap();
+        shuffle(&mut Replay(&[]), &mut [] as &mut [u8]).unwrap();
+        assert_eq!(choose(&mut Replay(&[]), &[1]).unwrap(), Some(&1));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn sample_indices_is_uniform() {
+        use super::sample_indices;
+        // Both algorithms, with every index at every position equally likely.
+        for &(n, k) in &[(10, 3), (5, 4)] {
+            let mut source = SplitMix(3);
+            let mut counts = vec![0; n * k];
+            for _ in 0..20_000 * n {
+                let indices = sample_indices(&mut source, n, k).unwrap();
+                assert_eq!(indices.len(), k);
+                for (position, &index) in indices.iter().enumerate() {
+                    assert!(!indices[..position].contains(&index));
+                    counts[position * n + index] += 1;
+                }
+            }
+            assert_uniform(&counts);
+        }
+        assert!(sample_indices(&mut Replay(&[]), 5, 0).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    #[should_panic(expected = "cannot sample")]
+    fn sample_indices_rejects_too_many() {
+        let _ = super::sample_indices(&mut Replay(&[]), 3, 4);
+    }
+}
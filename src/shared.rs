@@ -0,0 +1,89 @@
+//! A lazily initialized [`RdRand`] handle shared by the whole program.
+use crate::{ErrorCode, RdRand};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+const UNKNOWN: u8 = 0;
+const SUPPORTED: u8 = 1;
+const UNSUPPORTED: u8 = 2;
+
+/// Whether the `rdrand` instruction is supported, once it has been detected.
+static SUPPORT: AtomicU8 = AtomicU8::new(UNKNOWN);
+static SHARED: RdRand = RdRand(());
+
+impl RdRand {
+    /// Get a handle to the generator shared by the whole program.
+    ///
+    /// Support for the instruction is detected on the first call only; the result, including the
+    /// error if the instruction is not supported, is cached for all later calls. The cache is a
+    /// single atomic, so this works the same with and without the `std` feature. Threads that
+    /// race on the first call may each run the detection, which is harmless.
+    pub fn shared() -> Result<&'static RdRand, ErrorCode> {
+        match SUPPORT.load(Ordering::Relaxed) {
+            SUPPORTED => Ok(&SHARED),
+            UNSUPPORTED => Err(ErrorCode::UnsupportedInstruction),
+            _ => match RdRand::new() {
+                Ok(_) => {
+                    SUPPORT.store(SUPPORTED, Ordering::Relaxed);
+                    Ok(&SHARED)
+                }
+                Err(code) => {
+                    SUPPORT.store(UNSUPPORTED, Ordering::Relaxed);
+                    Err(code)
+                }
+            },
+        }
+    }
+}
+
+/// Get a handle to the [`RdRand`] generator shared by the whole program.
+///
+/// This is the same as [`RdRand::shared`].
+pub fn global() -> Result<&'static RdRand, ErrorCode> {
+    RdRand::shared()
+}
+
+/// Fill `dest` with random bytes from the shared [`RdRand`] generator.
+///
+/// An error is returned if the `rdrand` instruction is not supported or fails. See
+/// [`RdRand::try_fill_bytes`] for details.
+pub fn fill(dest: &mut [u8]) -> Result<(), ErrorCode> {
+    let mut rng = *RdRand::shared()?;
+    rng.try_fill_bytes(dest)
+}
+
+/// Generate a random `u64` with the shared [`RdRand`] generator.
+///
+/// An error is returned if the `rdrand` instruction is not supported or fails.
+pub fn u64() -> Result<u64, ErrorCode> {
+    RdRand::shared()?.try_next_u64()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fill, global, SUPPORT, UNKNOWN};
+    use crate::{ErrorCode, RdRand};
+    use core::sync::atomic::Ordering;
+
+    #[test]
+    fn caches_detection() {
+        let first = global();
+        assert_ne!(SUPPORT.load(Ordering::Relaxed), UNKNOWN);
+        assert_eq!(first.is_ok(), RdRand::new().is_ok());
+        match (first, RdRand::shared()) {
+            (Ok(a), Ok(b)) => assert!(core::ptr::eq(a, b)),
+            (Err(_), Err(code)) => assert!(matches!(code, ErrorCode::UnsupportedInstruction)),
+            _ => panic!("the cached result changed"),
+        }
+    }
+
+    #[test]
+    fn free_functions_work() {
+        if global().is_err() {
+            return;
+        }
+        let mut buf = [0; 64];
+        fill(&mut buf).unwrap();
+        assert_ne!(buf, [0; 64]);
+        assert_ne!(super::u64().unwrap(), super::u64().unwrap());
+    }
+}
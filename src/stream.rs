@@ -0,0 +1,183 @@
+use crate::fork::ForkGuard;
+use crate::{EntropySource, ErrorCode, RdSeed, MAX_EMPTY_POLLS};
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures_core::Stream;
+
+/// A [`Stream`] of `N`-byte blocks of random data produced by [`RdSeed`] (or another
+/// [`EntropySource`]).
+///
+/// The stream never blocks the executor: whenever the source runs out of entropy, the task yields
+/// and resumes filling the block the next time it is polled. If the source produces no data for
+/// 128 consecutive polls, the stream yields an error and starts over with a fresh block.
+///
+/// The stream never ends.
+///
+/// This stream is only available with the `futures` feature enabled.
+pub struct RdStream<const N: usize, S = RdSeed> {
+    source: S,
+    block: [u8; N],
+    /// The bytes `block[..filled]` have been produced already.
+    filled: usize,
+    empty_polls: u32,
+    fork: ForkGuard,
+}
+
+impl<const N: usize> RdStream<N> {
+    /// Create a new stream of blocks produced by [`RdSeed`].
+    ///
+    /// An error is returned if the `rdseed` instruction is not supported.
+    pub fn new() -> Result<Self, ErrorCode> {
+        RdSeed::new().map(Self::with_source)
+    }
+}
+
+impl<const N: usize, S: EntropySource> RdStream<N, S> {
+    /// Create a new stream of blocks produced by `source`.
+    pub fn with_source(source: S) -> Self {
+        RdStream {
+            source,
+            block: [0; N],
+            filled: 0,
+            empty_polls: 0,
+            fork: ForkGuard::new(),
+        }
+    }
+
+    /// Unwrap the source, discarding any partially produced block.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<const N: usize, S: EntropySource + Unpin> Stream for RdStream<N, S> {
+    type Item = Result<[u8; N], ErrorCode>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.fork.forked() {
+            // The other process completes the same partially produced block.
+            this.filled = 0;
+            this.fork.reset();
+        }
+        let produced = match this
+            .source
+            .try_fill_bytes_nonblocking(&mut this.block[this.filled..])
+        {
+            Ok(produced) => produced,
+            Err(code) => {
+                this.filled = 0;
+                return Poll::Ready(Some(Err(code)));
+            }
+        };
+        this.filled += produced;
+        if this.filled == N {
+            let block = this.block;
+            this.block = [0; N];
+            this.filled = 0;
+            this.empty_polls = 0;
+            return Poll::Ready(Some(Ok(block)));
+        }
+        if produced == 0 {
+            this.empty_polls += 1;
+            if this.empty_polls >= MAX_EMPTY_POLLS {
+                this.filled = 0;
+                this.empty_polls = 0;
+                return Poll::Ready(Some(Err(ErrorCode::HardwareFailure)));
+            }
+        } else {
+            this.empty_polls = 0;
+        }
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RdStream;
+    use crate::mock::{Replay, Trickle};
+    use crate::{ErrorCode, MAX_EMPTY_POLLS};
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+    use futures_core::Stream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+
+    /// Counts how many times the task has been woken.
+    #[derive(Default)]
+    struct Wakes(AtomicUsize);
+
+    impl Wake for Wakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn poll<S: Stream + Unpin>(stream: &mut S, waker: &Waker) -> Poll<Option<S::Item>> {
+        Pin::new(stream).poll_next(&mut Context::from_waker(waker))
+    }
+
+    #[test]
+    fn yields_blocks() {
+        let wakes = Arc::new(Wakes::default());
+        let waker = Waker::from(wakes.clone());
+        let input = [1, 2, 3, 4, 5, 6];
+        let mut stream = RdStream::<3, _>::with_source(Replay(&input));
+        assert!(matches!(
+            poll(&mut stream, &waker),
+            Poll::Ready(Some(Ok([1, 2, 3])))
+        ));
+        assert!(matches!(
+            poll(&mut stream, &waker),
+            Poll::Ready(Some(Ok([4, 5, 6])))
+        ));
+        assert!(matches!(
+            poll(&mut stream, &waker),
+            Poll::Ready(Some(Err(_)))
+        ));
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn yields_instead_of_spinning() {
+        let wakes = Arc::new(Wakes::default());
+        let waker = Waker::from(wakes.clone());
+        let input = [1, 2, 3];
+        let mut stream = RdStream::<3, _>::with_source(Trickle(Replay(&input)));
+        assert!(poll(&mut stream, &waker).is_pending());
+        assert!(poll(&mut stream, &waker).is_pending());
+        assert!(matches!(
+            poll(&mut stream, &waker),
+            Poll::Ready(Some(Ok([1, 2, 3])))
+        ));
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 2);
+
+        for _ in 1..MAX_EMPTY_POLLS {
+            assert!(poll(&mut stream, &waker).is_pending());
+        }
+        assert!(matches!(
+            poll(&mut stream, &waker),
+            Poll::Ready(Some(Err(ErrorCode::HardwareFailure)))
+        ));
+    }
+
+    #[test]
+    fn rdstream_works() {
+        let _ = RdStream::<32>::new().map(|mut stream| {
+            let waker = Waker::from(Arc::new(Wakes::default()));
+            let mut blocks = 0;
+            while blocks < 16 {
+                match poll(&mut stream, &waker) {
+                    Poll::Ready(Some(block)) => {
+                        block.unwrap();
+                        blocks += 1;
+                    }
+                    Poll::Ready(None) => unreachable!(),
+                    Poll::Pending => {}
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,275 @@
+//! A buffered [`RdRand`] handle for many small draws.
+#[cfg(feature = "rand_core_06")]
+use crate::busy_loop_fail;
+use crate::fork::ForkGuard;
+use crate::secret::wipe;
+use crate::{EntropySource, ErrorCode, RdRand};
+#[cfg(feature = "rand_core_06")]
+use rand_core_06::{CryptoRng, Error, RngCore};
+use std::cell::RefCell;
+
+/// The size of the per-thread buffer in bytes.
+const BUFFER_LEN: usize = 512;
+
+/// Random bytes of which `bytes[index..]` have not been handed out yet.
+struct Buffer {
+    bytes: [u8; BUFFER_LEN],
+    index: usize,
+    fork: ForkGuard,
+}
+
+impl Buffer {
+    /// Fill `dest` from the buffer, refilling it from `source` whenever it runs empty.
+    ///
+    /// Every byte is wiped from the buffer as it is handed out. Requests of at least a whole
+    /// buffer that find it empty are served from `source` directly. After a fork, the bytes left
+    /// in the buffer are discarded, as the other process may hand them out as well.
+    fn fill<S: EntropySource>(
+        &mut self,
+        source: &mut S,
+        mut dest: &mut [u8],
+    ) -> Result<(), ErrorCode> {
+        if self.fork.forked() {
+            wipe(&mut self.bytes);
+            self.index = BUFFER_LEN;
+            self.fork.reset();
+        }
+        while !dest.is_empty() {
+            if self.index == BUFFER_LEN {
+                if dest.len() >= BUFFER_LEN {
+                    return source.try_fill_bytes(dest);
+                }
+                source.try_fill_bytes(&mut self.bytes)?;
+                self.index = 0;
+            }
+            let available = &mut self.bytes[self.index..];
+            let len = available.len().min(dest.len());
+            let (copy_dest, dest_leftover) = { dest }.split_at_mut(len);
+            copy_dest.copy_from_slice(&available[..len]);
+            wipe(&mut available[..len]);
+            self.index += len;
+            dest = dest_leftover;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        wipe(&mut self.bytes);
+    }
+}
+
+thread_local! {
+    static BUFFER: RefCell<Buffer> = const {
+        RefCell::new(Buffer {
+            bytes: [0; BUFFER_LEN],
+            index: BUFFER_LEN,
+            fork: ForkGuard::empty(),
+        })
+    };
+}
+
+/// A handle to the [`RdRand`] generator that serves small draws from a per-thread buffer.
+///
+/// Every execution of the instruction has a high latency, which dominates when many small values
+/// are drawn. This handle instead refills a buffer of 512 bytes per thread with the same word loop
+/// as [`RdRand::try_fill_bytes`] and serves `u32`s, `u64`s and short byte strings from it. Every
+/// byte is wiped from the buffer as soon as it is handed out.
+///
+/// Handles are obtained with [`thread_rdrand`] and can be copied freely; all handles used on the
+/// same thread share its buffer.
+///
+/// This handle is only available with the `std` feature enabled.
+#[derive(Clone, Copy)]
+pub struct ThreadRdRand {
+    rng: RdRand,
+}
+
+/// Get a handle to the [`RdRand`] generator that is buffered per thread.
+///
+/// An error is returned if the `rdrand` instruction is not supported. Support is detected only
+/// once, as for [`RdRand::shared`].
+///
+/// This function is only available with the `std` feature enabled.
+pub fn thread_rdrand() -> Result<ThreadRdRand, ErrorCode> {
+    RdRand::shared().map(|&rng| ThreadRdRand { rng })
+}
+
+impl ThreadRdRand {
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// If an error is returned, the caller should assume that an non-recoverable hardware failure
+    /// has occured and use another random number genrator instead.
+    pub fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        let rng = &mut self.rng;
+        match BUFFER.try_with(|buffer| buffer.borrow_mut().fill(rng, dest)) {
+            Ok(result) => result,
+            // The buffer of this thread has already been destroyed.
+            Err(_) => rng.try_fill_bytes(dest),
+        }
+    }
+
+    /// Generate a single random `u32` value.
+    pub fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        EntropySource::try_next_u32(self)
+    }
+
+    /// Generate a single random `u64` value.
+    pub fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        EntropySource::try_next_u64(self)
+    }
+}
+
+impl EntropySource for ThreadRdRand {
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        ThreadRdRand::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl RngCore for ThreadRdRand {
+    /// Generate a single random `u32` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_next_u32` would return an error.
+    fn next_u32(&mut self) -> u32 {
+        match ThreadRdRand::try_next_u32(self) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Generate a single random `u64` value.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_next_u64` would return an error.
+    fn next_u64(&mut self) -> u64 {
+        match ThreadRdRand::try_next_u64(self) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    ///
+    /// # Panic
+    ///
+    /// This method will panic any time `try_fill_bytes` would return an error.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match ThreadRdRand::try_fill_bytes(self, dest) {
+            Ok(result) => result,
+            Err(c) => busy_loop_fail(c),
+        }
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        ThreadRdRand::try_fill_bytes(self, dest).map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "rand_core_06")]
+impl CryptoRng for ThreadRdRand {}
+
+#[cfg(feature = "rand_core_09")]
+impl rand_core_09::TryRngCore for ThreadRdRand {
+    type Error = ErrorCode;
+
+    /// Generate a single random `u32` value.
+    fn try_next_u32(&mut self) -> Result<u32, ErrorCode> {
+        ThreadRdRand::try_next_u32(self)
+    }
+
+    /// Generate a single random `u64` value.
+    fn try_next_u64(&mut self) -> Result<u64, ErrorCode> {
+        ThreadRdRand::try_next_u64(self)
+    }
+
+    /// Fill a buffer `dest` with random data.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ErrorCode> {
+        ThreadRdRand::try_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(feature = "rand_core_09")]
+impl rand_core_09::TryCryptoRng for ThreadRdRand {}
+
+#[cfg(test)]
+mod test {
+    use super::{thread_rdrand, Buffer, BUFFER_LEN};
+    use crate::fork::ForkGuard;
+    use crate::mock::Replay;
+
+    #[test]
+    fn serves_from_buffer_and_wipes() {
+        let input: Vec<u8> = (0..BUFFER_LEN * 2).map(|i| i as u8 | 1).collect();
+        let mut source = Replay(&input);
+        let mut buffer = Buffer {
+            bytes: [0; BUFFER_LEN],
+            index: BUFFER_LEN,
+            fork: ForkGuard::new(),
+        };
+        let mut dest = [0; 3];
+        buffer.fill(&mut source, &mut dest).unwrap();
+        assert_eq!(dest, [1, 1, 3]);
+        assert_eq!(source.0.len(), BUFFER_LEN);
+        assert_eq!(buffer.bytes[..3], [0; 3]);
+        assert_eq!(buffer.bytes[3], 3);
+
+        // Draws continue where the last one stopped and span refills.
+        let mut dest = [0; BUFFER_LEN];
+        buffer.fill(&mut source, &mut dest).unwrap();
+        assert_eq!(&dest[..], &input[3..BUFFER_LEN + 3]);
+        assert!(source.0.is_empty());
+        assert_eq!(buffer.index, 3);
+        assert_eq!(buffer.bytes[..3], [0; 3]);
+    }
+
+    #[test]
+    fn bypasses_empty_buffer_for_large_draws() {
+        let input = [0x11; BUFFER_LEN + 1];
+        let mut buffer = Buffer {
+            bytes: [0; BUFFER_LEN],
+            index: BUFFER_LEN,
+            fork: ForkGuard::new(),
+        };
+        let mut dest = [0; BUFFER_LEN + 1];
+        buffer.fill(&mut Replay(&input), &mut dest).unwrap();
+        assert_eq!(dest, input);
+        assert_eq!(buffer.index, BUFFER_LEN);
+    }
+
+    #[test]
+    fn discards_after_fork() {
+        let input: Vec<u8> = (0..BUFFER_LEN * 2)
+            .map(|i| (i / BUFFER_LEN) as u8 + 1)
+            .collect();
+        let mut source = Replay(&input);
+        let mut buffer = Buffer {
+            bytes: [0; BUFFER_LEN],
+            index: BUFFER_LEN,
+            fork: ForkGuard::new(),
+        };
+        let mut dest = [0; 4];
+        buffer.fill(&mut source, &mut dest).unwrap();
+        assert_eq!(dest, [1; 4]);
+        buffer.fork = ForkGuard::stale();
+        buffer.fill(&mut source, &mut dest).unwrap();
+        assert_eq!(dest, [2; 4]);
+        assert!(!buffer.fork.forked());
+    }
+
+    #[test]
+    fn thread_rdrand_works() {
+        let _ = thread_rdrand().map(|mut rng| {
+            let first = rng.try_next_u64().unwrap();
+            assert_ne!(first, rng.try_next_u64().unwrap());
+            let mut buf = [0; 1000];
+            rng.try_fill_bytes(&mut buf).unwrap();
+            assert_ne!(&buf[..], &[0; 1000][..]);
+        });
+    }
+}
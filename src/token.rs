@@ -0,0 +1,317 @@
+use crate::{BitSource, EntropySource, ErrorCode, RdRand};
+use std::string::String;
+use std::vec::Vec;
+
+/// The characters random tokens are made of.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alphabet {
+    chars: Vec<char>,
+}
+
+impl Alphabet {
+    /// Create an alphabet of the distinct characters of `chars`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `chars` contains fewer than two characters or any character more
+    /// than once.
+    pub fn new(chars: &str) -> Self {
+        let chars: Vec<char> = chars.chars().collect();
+        assert!(
+            chars.len() >= 2,
+            "an alphabet needs at least two characters"
+        );
+        for (i, c) in chars.iter().enumerate() {
+            assert!(
+                !chars[..i].contains(c),
+                "duplicate character {:?} in alphabet",
+                c
+            );
+        }
+        Alphabet { chars }
+    }
+
+    /// The decimal digits.
+    pub fn digits() -> Self {
+        Self::new("0123456789")
+    }
+
+    /// Douglas Crockford's base 32 alphabet, which avoids the easily confused letters I, L, O and
+    /// U.
+    pub fn crockford_base32() -> Self {
+        Self::new("0123456789ABCDEFGHJKMNPQRSTVWXYZ")
+    }
+
+    /// The URL and filename safe base 64 alphabet of RFC 4648.
+    pub fn base64url() -> Self {
+        Self::new("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_")
+    }
+
+    /// The number of characters in the alphabet.
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// Always `false`, as alphabets have at least two characters.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    fn index(&self, c: char) -> Option<usize> {
+        self.chars.iter().position(|&x| x == c)
+    }
+
+    /// The index of the Luhn mod N check character of the characters with indices `codes`.
+    fn check_code(&self, codes: &[usize]) -> usize {
+        let n = self.len();
+        let sum: usize = codes
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, &code)| {
+                let addend = if i % 2 == 0 { code * 2 } else { code };
+                addend / n + addend % n
+            })
+            .sum();
+        (n - sum % n) % n
+    }
+}
+
+/// A generator of random tokens, such as API keys, recovery codes or passwords, drawn from
+/// [`RdRand`] (or another [`EntropySource`]).
+///
+/// Every character of a token is drawn uniformly from an [`Alphabet`] without modulo bias: the
+/// generator draws the smallest number of bits that can index the alphabet and rejects the indices
+/// that are out of range. For alphabets whose size is a power of two, such as base 32 and base 64,
+/// no bits are rejected at all.
+///
+/// By default tokens carry 128 bits of entropy. Optionally, a Luhn mod N check character from the
+/// same alphabet can be appended and the characters can be split into groups.
+///
+/// This generator is only available with the `std` feature enabled.
+pub struct TokenGenerator<S = RdRand> {
+    bits: BitSource<S>,
+    alphabet: Alphabet,
+    length: usize,
+    checksum: bool,
+    grouping: Option<(usize, char)>,
+}
+
+impl TokenGenerator {
+    /// Create a new generator of tokens over `alphabet` drawing from [`RdRand`].
+    ///
+    /// An error is returned if the `rdrand` instruction is not supported.
+    pub fn new(alphabet: Alphabet) -> Result<Self, ErrorCode> {
+        RdRand::new().map(|source| Self::with_source(source, alphabet))
+    }
+}
+
+impl<S: EntropySource> TokenGenerator<S> {
+    /// Create a new generator of tokens over `alphabet` drawing from `source`.
+    pub fn with_source(source: S, alphabet: Alphabet) -> Self {
+        let mut generator = TokenGenerator {
+            bits: BitSource::with_source(source),
+            alphabet,
+            length: 0,
+            checksum: false,
+            grouping: None,
+        };
+        generator.set_entropy_bits(128);
+        generator
+    }
+
+    /// Set the number of random characters in every token.
+    pub fn set_length(&mut self, length: usize) {
+        self.length = length;
+    }
+
+    /// Set the number of random characters in every token to the smallest number that carries at
+    /// least `bits` bits of entropy.
+    pub fn set_entropy_bits(&mut self, bits: u32) {
+        let per_char = (self.alphabet.len() as f64).log2();
+        self.length = (f64::from(bits) / per_char).ceil() as usize;
+    }
+
+    /// Set whether a Luhn mod N check character is appended to every token.
+    ///
+    /// The check character does not add any entropy. Use [`is_valid`] to verify it.
+    ///
+    /// [`is_valid`]: TokenGenerator::is_valid
+    pub fn set_checksum(&mut self, checksum: bool) {
+        self.checksum = checksum;
+    }
+
+    /// Split tokens into groups of `size` characters separated by `separator`.
+    ///
+    /// A `size` of 0 disables grouping.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `separator` belongs to the alphabet, as it could not be told apart
+    /// from the random characters.
+    pub fn set_grouping(&mut self, size: usize, separator: char) {
+        assert!(
+            self.alphabet.index(separator).is_none(),
+            "separator {:?} belongs to the alphabet",
+            separator
+        );
+        self.grouping = if size == 0 {
+            None
+        } else {
+            Some((size, separator))
+        };
+    }
+
+    /// The number of random characters in every token.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// The number of bits of entropy of every token.
+    pub fn entropy_bits(&self) -> f64 {
+        self.length as f64 * (self.alphabet.len() as f64).log2()
+    }
+
+    /// Generate a new token.
+    ///
+    /// If an error is returned, the caller should assume that an non-recoverable hardware failure
+    /// has occured and use another random number genrator instead.
+    pub fn try_generate(&mut self) -> Result<String, ErrorCode> {
+        let n = self.alphabet.len();
+        let width = usize::BITS - (n - 1).leading_zeros();
+        let mut codes = Vec::with_capacity(self.length + 1);
+        while codes.len() < self.length {
+            let code = self.bits.try_next_bits(width)? as usize;
+            if code < n {
+                codes.push(code);
+            }
+        }
+        if self.checksum {
+            codes.push(self.alphabet.check_code(&codes));
+        }
+        let mut token = String::new();
+        for (i, &code) in codes.iter().enumerate() {
+            match self.grouping {
+                Some((size, separator)) if i > 0 && i % size == 0 => token.push(separator),
+                _ => {}
+            }
+            token.push(self.alphabet.chars[code]);
+        }
+        Ok(token)
+    }
+
+    /// Check whether `token` could have been generated by this generator.
+    ///
+    /// Separators are ignored. The token must have the configured length and, if checksums are
+    /// enabled, a valid check character.
+    pub fn is_valid(&self, token: &str) -> bool {
+        let separator = self.grouping.map(|(_, separator)| separator);
+        let codes: Option<Vec<usize>> = token
+            .chars()
+            .filter(|&c| Some(c) != separator)
+            .map(|c| self.alphabet.index(c))
+            .collect();
+        let codes = match codes {
+            Some(codes) => codes,
+            None => return false,
+        };
+        if !self.checksum {
+            return codes.len() == self.length;
+        }
+        match codes.split_last() {
+            Some((&check, codes)) if codes.len() == self.length => {
+                check == self.alphabet.check_code(codes)
+            }
+            _ => false,
+        }
+    }
+
+    /// Unwrap the source.
+    pub fn into_inner(self) -> S {
+        self.bits.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Alphabet, TokenGenerator};
+    use crate::mock::{hex, Replay};
+
+    #[test]
+    fn rejects_out_of_range_indices() {
+        // The digits take 4 bits each, least significant first: 1, 2, 3, 4, 15, 0, ...
+        let input = hex("21430f0000000000");
+        let mut generator = TokenGenerator::with_source(Replay(&input), Alphabet::digits());
+        generator.set_length(5);
+        assert_eq!(generator.try_generate().unwrap(), "12340");
+    }
+
+    #[test]
+    fn lengths() {
+        let mut generator = TokenGenerator::with_source(Replay(&[]), Alphabet::crockford_base32());
+        assert_eq!(generator.length(), 26);
+        generator.set_entropy_bits(80);
+        assert_eq!(generator.length(), 16);
+        let mut generator = TokenGenerator::with_source(Replay(&[]), Alphabet::digits());
+        generator.set_entropy_bits(20);
+        assert_eq!(generator.length(), 7);
+        assert!(generator.entropy_bits() >= 20.0);
+    }
+
+    #[test]
+    fn luhn_checksum() {
+        // The example from the description of the Luhn algorithm.
+        let codes = [7, 9, 9, 2, 7, 3, 9, 8, 7, 1];
+        assert_eq!(Alphabet::digits().check_code(&codes), 3);
+
+        let input = [0x42; 16];
+        let mut generator = TokenGenerator::with_source(Replay(&input), Alphabet::base64url());
+        generator.set_length(10);
+        generator.set_checksum(true);
+        generator.set_grouping(4, ' ');
+        let token = generator.try_generate().unwrap();
+        assert_eq!(token.len(), 11 + 2);
+        assert!(generator.is_valid(&token));
+        // Every substitution of a single character is detected.
+        let alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+        for (i, original) in token.char_indices().filter(|&(_, c)| c != ' ') {
+            for replacement in alphabet.chars().filter(|&c| c != original) {
+                let mut substituted = token.clone();
+                substituted.replace_range(i..i + 1, replacement.encode_utf8(&mut [0; 4]));
+                assert!(!generator.is_valid(&substituted), "{}", substituted);
+            }
+        }
+        assert!(!generator.is_valid(&token[1..]));
+    }
+
+    #[test]
+    fn grouping() {
+        let input = [0; 8];
+        let mut generator = TokenGenerator::with_source(Replay(&input), Alphabet::new("ab"));
+        generator.set_length(10);
+        generator.set_grouping(4, '-');
+        assert_eq!(generator.try_generate().unwrap(), "aaaa-aaaa-aa");
+    }
+
+    #[test]
+    #[should_panic(expected = "belongs to the alphabet")]
+    fn rejects_separator_in_alphabet() {
+        let mut generator = TokenGenerator::with_source(Replay(&[]), Alphabet::new("ab-"));
+        generator.set_grouping(4, '-');
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate")]
+    fn rejects_duplicates() {
+        Alphabet::new("abca");
+    }
+
+    #[test]
+    fn token_generator_works() {
+        let _ = TokenGenerator::new(Alphabet::base64url()).map(|mut generator| {
+            let token = generator.try_generate().unwrap();
+            assert_eq!(token.len(), 22);
+            assert!(generator.is_valid(&token));
+        });
+    }
+}
@@ -0,0 +1,123 @@
+//! Check that parent and child processes never produce the same output after a `fork`.
+#![cfg(all(unix, feature = "std"))]
+
+use rdrand::{thread_rdrand, BitSource, RdReader};
+use std::io::Read;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Draw 8 bytes with `draw` once, fork, and draw 8 more bytes in both processes.
+///
+/// The draws must be chosen so that the generator still holds buffered output or state after the
+/// first one, which the draws after the fork would reuse if the fork went undetected.
+fn assert_diverges(name: &str, mut draw: impl FnMut() -> [u8; 8]) {
+    draw();
+    let mut fds = [0; 2];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    let pid = unsafe { libc::fork() };
+    assert!(pid >= 0, "fork failed");
+    if pid == 0 {
+        // Don't let a panic unwind into the test harness of the child.
+        let status = match panic::catch_unwind(AssertUnwindSafe(&mut draw)) {
+            Ok(bytes) => {
+                let written = unsafe { libc::write(fds[1], bytes.as_ptr().cast(), bytes.len()) };
+                (written != bytes.len() as isize) as i32
+            }
+            Err(_) => 1,
+        };
+        unsafe { libc::_exit(status) };
+    }
+    let parent = draw();
+    unsafe { libc::close(fds[1]) };
+    let mut child = [0u8; 8];
+    let mut read = 0;
+    while read < child.len() {
+        let len = unsafe {
+            libc::read(
+                fds[0],
+                child[read..].as_mut_ptr().cast(),
+                child.len() - read,
+            )
+        };
+        if len <= 0 {
+            break;
+        }
+        read += len as usize;
+    }
+    unsafe { libc::close(fds[0]) };
+    let mut status = 0;
+    assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+    assert!(
+        libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0,
+        "{}: the child failed",
+        name
+    );
+    assert_eq!(read, child.len(), "{}: the child produced no output", name);
+    assert_ne!(parent, child, "{}: the child repeated the parent", name);
+}
+
+#[test]
+fn stateful_generators_diverge_after_fork() {
+    if let Ok(mut rng) = thread_rdrand() {
+        assert_diverges("thread_rdrand", || {
+            rng.try_next_u64().unwrap().to_ne_bytes()
+        });
+    }
+    if let Ok(mut bits) = BitSource::new() {
+        // One word covers the bit drawn here and both draws of 31 bits, so the draws after the
+        // fork come from the buffered word.
+        bits.try_next_bits(1).unwrap();
+        assert_diverges("BitSource", || {
+            bits.try_next_bits(31).unwrap().to_ne_bytes()
+        });
+        assert_eq!(bits.words_consumed(), 1);
+    }
+    if let Ok(mut reader) = RdReader::new() {
+        assert_diverges("RdReader", || {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes).unwrap();
+            bytes
+        });
+    }
+    #[cfg(feature = "chacha")]
+    if let Ok(mut rng) = rdrand::ChaChaRdRng::new() {
+        assert_diverges("ChaChaRdRng", || {
+            let mut bytes = [0; 8];
+            rng.try_fill_bytes(&mut bytes).unwrap();
+            bytes
+        });
+    }
+    #[cfg(feature = "drbg")]
+    if let Ok(mut rng) = rdrand::drbg::HashDrbgSha256::new(b"fork") {
+        assert_diverges("HashDrbgSha256", || {
+            let mut bytes = [0; 8];
+            rng.try_fill_bytes(&mut bytes).unwrap();
+            bytes
+        });
+    }
+    #[cfg(feature = "drbg")]
+    if let Ok(mut rng) = rdrand::drbg::HmacDrbgSha256::new(b"fork") {
+        assert_diverges("HmacDrbgSha256", || {
+            let mut bytes = [0; 8];
+            rng.try_fill_bytes(&mut bytes).unwrap();
+            bytes
+        });
+    }
+    #[cfg(all(feature = "rand_core_06", feature = "chacha"))]
+    if let Ok(mut rng) = rdrand::Reseeding::<rand_chacha::ChaCha20Core>::new(1 << 20) {
+        // The first draw leaves most of a block of the inner generator buffered.
+        assert_diverges("Reseeding", || {
+            let mut bytes = [0; 8];
+            rng.try_fill_bytes(&mut bytes).unwrap();
+            bytes
+        });
+    }
+    #[cfg(feature = "fortuna")]
+    {
+        let mut rng = rdrand::Fortuna::new().unwrap();
+        assert_diverges("Fortuna", || {
+            let mut bytes = [0; 8];
+            rng.try_fill_bytes(&mut bytes).unwrap();
+            bytes
+        });
+    }
+}